@@ -3,16 +3,13 @@ use serde_json::{Map, Number, Value};
 use rparse::parser::{
     choice::optional,
     combinator::attempt,
-    item::{
-        ascii::{digit, whitespace},
-        item, one_of, satisfy,
-    },
+    item::{ascii::whitespace, item, one_of, satisfy},
     parser,
-    range::range,
-    repeat::{many, many1, sep_by},
+    range::{range, take_while, take_while1},
+    repeat::{many, sep_by},
     seq::between,
 };
-use rparse::stream::{RangeStream, StreamItem};
+use rparse::stream::{StreamItem, TextRange};
 use rparse::{ParseResult, Parser, Stream};
 
 use common::quoted_string;
@@ -35,36 +32,30 @@ fn boolean<S: Stream>() -> impl JSONParser<S> {
 }
 
 fn number<S: Stream>() -> impl JSONParser<S> {
+    let is_digit = |b: &S::Item| b.is_ascii_digit();
     let non_zero_digit = satisfy(|&b: &S::Item| b.is_ascii_digit() && b != b'0'.into());
     let exponent = one_of([b'e', b'E'])
-        .then(optional(item(b'-')))
-        .extend(many1(digit()));
-
-    concat![
-        // an optional minus sign,
-        optional(item(b'-')).wrap(),
-        // followed by either
-        choice![
-            // a zero
-            item(b'0').wrap(),
-            // or a non-zero digit followed by zero or more digits
-            non_zero_digit.wrap().extend(many(digit())),
-        ],
-        // followed by an optional decimal point and zero or more digits
-        optional(item(b'.').wrap().extend(many1(digit()))),
+        .skip(optional(item(b'-')))
+        .skip(take_while1(is_digit));
+
+    // an optional minus sign,
+    optional(item(b'-'))
+        // followed by either a zero, or a non-zero digit followed by zero or more digits
+        .with(item(b'0').or(non_zero_digit.skip(take_while(is_digit))))
+        // followed by an optional decimal point and one or more digits
+        .skip(optional(item(b'.').skip(take_while1(is_digit))))
         // followed by an optional exponent
-        optional(exponent)
-    ]
-    .collect_string()
-    .from_str::<f64>()
-    .and_then(|n: f64, stream: S| {
-        let num = Value::Number(match Number::from_f64(n) {
-            Some(num) => num,
-            None => return stream.err(format!("{} is not a valid JSON number", n).into()),
-        });
-        stream.ok(num)
-    })
-    .expect("a number")
+        .skip(optional(exponent))
+        .recognize()
+        .from_str::<f64>()
+        .and_then(|n: f64, stream: S| {
+            let num = Value::Number(match Number::from_f64(n) {
+                Some(num) => num,
+                None => return stream.err(format!("{} is not a valid JSON number", n).into()),
+            });
+            stream.ok(num)
+        })
+        .expect("a number")
 }
 
 fn string<S: Stream>() -> impl JSONParser<S> {
@@ -122,6 +113,11 @@ mod test {
                 (",", 45),
             ),
         });
+        // the branch that got furthest into the input is reported
+        test_parser!(IndexedStream<&str> => Value | json_value(), {
+            "nul" => err(Error::eoi().at(3).spanning((0, 3)).expected_range("null")),
+            "fals" => err(Error::eoi().at(4).spanning((0, 4)).expected("a boolean value")),
+        });
     }
 
     #[test]
@@ -130,8 +126,8 @@ mod test {
             "null" => ok(json!(null), ("", 4)),
             "null, " => ok(json!(null), (", ", 4)),
             "" => err(Error::eoi().at(0).expected_range("null")),
-            "nul" => err(Error::eoi().at(3).expected_range("null")),
-            " null" => err(Error::item(' ').at(0).expected_range("null")),
+            "nul" => err(Error::eoi().at(3).spanning((0, 3)).expected_range("null")),
+            " null" => err(Error::item(' ').at(0).spanning((0, 1)).expected_range("null")),
         });
     }
 
@@ -142,9 +138,8 @@ mod test {
             "false" => ok(json!(false), ("", 5)),
             "true, " => ok(json!(true), (", ", 4)),
             "" => err(Error::eoi().at(0).expected("a boolean value")),
-            // FIXME: the error be eoi().at(3)
-            "tru" => err(Error::item('t').at(0).expected("a boolean value")),
-            " false" => err(Error::item(' ').at(0).expected("a boolean value")),
+            "tru" => err(Error::eoi().at(3).spanning((0, 3)).expected("a boolean value")),
+            " false" => err(Error::item(' ').at(0).spanning((0, 1)).expected("a boolean value")),
         });
     }
 
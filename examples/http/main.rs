@@ -40,10 +40,12 @@ Content-Type: application/json\r
 
 fn request<S: Stream>() -> impl Parser<Stream = S, Output = (RequestLine, Headers, Option<Value>)> {
     (
-        request_line().skip(crlf()),
-        headers(),
+        request_line().skip(crlf()).context("the request line"),
+        headers().context("the headers"),
         choice![
-            crlf().with(json_value().skip(crlf())).map(|v| Some(v)),
+            crlf()
+                .with(json_value().skip(crlf()).context("the body"))
+                .map(|v| Some(v)),
             many::<(), _>(crlf()).map(|_| None),
         ],
     )
@@ -60,7 +62,7 @@ fn main() {
         }
         Err((err, _)) => {
             println!("Parsing failed!");
-            dbg!(err);
+            println!("{}", err.report(INPUT));
         }
     };
 }
@@ -79,6 +79,7 @@ where
         range("OPTIONS"),
         range("CONNECT"),
     ]
+    .suggest()
     .as_string()
 }
 
@@ -191,7 +192,32 @@ mod test {
         });
 
         test_parser!(IndexedStream<&[u8]> => String | http_method(), {
-            &b"PUPPYDOG"[..] => err(Error::item(b'P').expected_one_of(into_expected).at(0)),
+            &b"PUPPYDOG"[..] => err(
+                Error::item(b'P')
+                    .expected_range(&b"PUT"[..])
+                    .at(2)
+                    .spanning((0, 3))
+            ),
+            &b"BREW"[..] => err(
+                Error::item(b'B')
+                    .expected_one_of(into_expected.clone())
+                    .at(0)
+                    .spanning((0, 1))
+            ),
+            &b"POTS /"[..] => err(
+                Error::item(b'T')
+                    .expected_range(&b"POST"[..])
+                    .at(2)
+                    .spanning((0, 3))
+                    .hint("did you mean `POST`?")
+            ),
+            &b"delete /"[..] => err(
+                Error::item(b'd')
+                    .expected_one_of(into_expected)
+                    .at(0)
+                    .spanning((0, 1))
+                    .hint("did you mean `DELETE`?")
+            ),
         });
 
         assert_eq!(
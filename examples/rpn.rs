@@ -31,6 +31,9 @@ fn main() {
         Ok((result, _)) => println!("{}", result),
         Err((err, _)) => {
             println!("{}", err);
+            if let Some(StackUnderflow(op)) = err.downcast_ref() {
+                println!("note: '{}' needs two operands", op);
+            }
             exit(1);
         }
     };
@@ -41,7 +44,7 @@ enum Token {
     Number(f64),
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 enum Op {
     Add,
     Sub,
@@ -49,10 +52,20 @@ enum Op {
     Div,
 }
 
+/// Error returned when an operator is applied without enough operands on the stack.
+#[derive(Debug)]
+struct StackUnderflow(Op);
+
+impl fmt::Display for StackUnderflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "operator '{}'", self.0)
+    }
+}
+
 impl Op {
     fn eval<S: Stream>(&self, stack: &mut Vec<f64>) -> Result<f64, Error<S>> {
         fn error<S: Stream>(op: &Op) -> Error<S> {
-            Error::from(format!("operator '{}'", op)).expected("a number")
+            Error::custom(StackUnderflow(*op)).expected("a number")
         }
         let (x, y) = (
             stack.pop().ok_or_else(|| error(self))?,
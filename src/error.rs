@@ -1,10 +1,16 @@
 //! Error and Result types that are used by parsers.
 
+use std::any::Any;
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::error::Error as StdError;
 use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 
-use stream::{Position, Stream, StreamItem};
+use serde_json::Value as Json;
+
+use stream::{Position, Span, Stream, Token, TokenStream};
 
 /// A parse result, returned by [`Parser::parse()`](parser::Parser::parse) and all other parsing
 /// functions.
@@ -15,7 +21,7 @@ pub type ParseResult<S, O> = Result<(Option<O>, S), (Error<S>, S)>;
 
 /// A parse error. This is the library error type.
 #[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Error<S: Stream> {
+pub struct Error<S: TokenStream> {
     /// The cause of the error.
     pub cause: Info<S>,
     /// The expected input. If `cause` describes the input that _caused_ the error, `expected`
@@ -23,9 +29,15 @@ pub struct Error<S: Stream> {
     pub expected: Option<Expected<S>>,
     /// The position in the input at which the error occurred.
     pub position: S::Position,
+    /// The region of input over which the error occurred, if it spans more than a single point.
+    pub span: Option<Span<S::Position>>,
+    /// Labels describing what was being parsed when the error occurred, innermost first.
+    pub context: Vec<Info<S>>,
+    /// Hints for fixing the input, such as suggested corrections.
+    pub hints: Vec<String>,
 }
 
-impl<S: Stream> Error<S> {
+impl<S: TokenStream> Error<S> {
     /// Create a new `Error`.
     ///
     /// This is the most general constructor for `Error`, setting all of its fields explicitly.
@@ -34,6 +46,9 @@ impl<S: Stream> Error<S> {
             cause,
             expected,
             position,
+            span: None,
+            context: Vec::new(),
+            hints: Vec::new(),
         }
     }
 
@@ -58,6 +73,13 @@ impl<S: Stream> Error<S> {
         Error::cause(Info::Range(range))
     }
 
+    /// Create a new `Error` caused by a user-defined error.
+    ///
+    /// Sets the other fields to their [`Default::default()`] values.
+    pub fn custom<E: Payload>(error: E) -> Self {
+        Error::cause(Info::custom(error))
+    }
+
     /// Create a new `Error` caused by reaching end-of-input.
     ///
     /// Sets the other fields to their [`Default::default()`] values.
@@ -65,15 +87,56 @@ impl<S: Stream> Error<S> {
         Error::cause(Info::EOI)
     }
 
+    /// Create a new `Error` caused by reaching the end of the input available so far on a
+    /// [partial stream](TokenStream::is_partial).
+    ///
+    /// Sets the other fields to their [`Default::default()`] values.
+    pub fn incomplete() -> Self {
+        Error::cause(Info::Incomplete)
+    }
+
+    /// Returns `true` if the error was caused by reaching the end of the input available so far
+    /// on a partial stream, in which case parsing may succeed once more input is available.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self.cause, Info::Incomplete)
+    }
+
     /// Set the error's `position`. Chainable.
     pub fn at<P: Into<S::Position>>(mut self, position: P) -> Self {
         self.position = position.into();
         self
     }
 
-    /// Sets the error's `expected` field. Chainable.
+    /// Set the error's `span`. Chainable.
+    ///
+    /// Empty spans are ignored, since they don't cover any input.
+    pub fn spanning<P: Into<Span<S::Position>>>(mut self, span: P) -> Self {
+        let span = span.into();
+        self.span = if span.start == span.end {
+            None
+        } else {
+            Some(span)
+        };
+        self
+    }
+
+    /// Push a label onto the error's `context` stack. Chainable.
+    ///
+    /// Labels should be pushed from the innermost to the outermost context.
+    pub fn context<I: Into<Info<S>>>(mut self, label: I) -> Self {
+        self.context.push(label.into());
+        self
+    }
+
+    /// Add a hint for fixing the input to the error's `hints`. Chainable.
+    pub fn hint<H: Into<String>>(mut self, hint: H) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
+
+    /// Sets the error's `expected` field, [normalizing](Expected::normalize) it. Chainable.
     pub fn expected<E: Into<Expected<S>>>(mut self, expected: E) -> Self {
-        self.expected = Some(expected.into());
+        self.expected = Some(expected.into().normalize());
         self
     }
 
@@ -83,7 +146,7 @@ impl<S: Stream> Error<S> {
         self
     }
 
-    /// Sets the error's `expected` to a specific [range](Stream::Range) of input. Chainable.
+    /// Sets the error's `expected` to a specific [range](TokenStream::Range) of input. Chainable.
     pub fn expected_range(mut self, range: S::Range) -> Self {
         self.expected = Some(Expected::Info(Info::Range(range)));
         self
@@ -100,13 +163,56 @@ impl<S: Stream> Error<S> {
         self
     }
 
+    /// Returns a reference to the user-defined error that caused this error, if it is of type `E`.
+    pub fn downcast_ref<E: Payload>(&self) -> Option<&E> {
+        self.cause.downcast_ref()
+    }
+
     /// Add the given [`Expected`] object to the error, merging with its existing `expected`.
     pub fn add_expected<E: Into<Expected<S>>>(&mut self, expected: E) {
         self.expected = Expected::merge_one_of(vec![self.expected.take(), Some(expected.into())]);
     }
+
+    /// Returns a machine-readable representation of the error:
+    ///
+    /// ```text
+    /// {
+    ///   "message": "parsing failed at index 5: unexpected item 'x': expected ...",
+    ///   "position": { "offset": 5 },
+    ///   "span": { "start": { "offset": 2 }, "end": { "offset": 5 } },
+    ///   "cause": { "kind": "item", "value": "x" },
+    ///   "expected": [{ "kind": "item", "value": "y" }, { "kind": "message", "value": "a digit" }],
+    ///   "context": ["a value", "a record"],
+    ///   "hints": []
+    /// }
+    /// ```
+    ///
+    /// Positions are represented by [`Position::to_json`], and `span` is `null` if the error
+    /// doesn't have one. `expected` is the flattened set of alternatives, where each alternative
+    /// is represented by [`Expected::to_json`]. `context` lists the context labels, innermost
+    /// first, and `hints` lists the error's hints.
+    pub fn to_json(&self) -> Json {
+        let expected = match &self.expected {
+            Some(Expected::OneOf(errors)) => errors.iter().map(Expected::to_json).collect(),
+            Some(expected) => vec![expected.to_json()],
+            None => vec![],
+        };
+        json!({
+            "message": self.to_string(),
+            "position": self.position.to_json(),
+            "span": self.span.as_ref().map(|span| json!({
+                "start": span.start.to_json(),
+                "end": span.end.to_json(),
+            })),
+            "cause": self.cause.to_json(),
+            "expected": expected,
+            "context": self.context.iter().map(|label| label.to_string()).collect::<Vec<_>>(),
+            "hints": self.hints,
+        })
+    }
 }
 
-impl<S: Stream> fmt::Display for Error<S> {
+impl<S: TokenStream> fmt::Display for Error<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
@@ -117,13 +223,20 @@ impl<S: Stream> fmt::Display for Error<S> {
         if let Some(expected) = &self.expected {
             write!(f, ": expected {}", expected)?;
         }
+        for (i, label) in self.context.iter().enumerate() {
+            let sep = if i == 0 { " " } else { ", " };
+            write!(f, "{}while parsing {}", sep, label)?;
+        }
+        for hint in &self.hints {
+            write!(f, " ({})", hint)?;
+        }
         Ok(())
     }
 }
 
-impl<S: Stream> StdError for Error<S> {}
+impl<S: TokenStream> StdError for Error<S> {}
 
-impl<S: Stream> From<Info<S>> for Error<S> {
+impl<S: TokenStream> From<Info<S>> for Error<S> {
     fn from(info: Info<S>) -> Self {
         Error::cause(info)
     }
@@ -137,42 +250,46 @@ impl<S: Stream> From<u8> for Error<S> {
 
 impl<S> From<char> for Error<S>
 where
-    S: Stream<Item = char>,
+    S: TokenStream<Item = char>,
 {
     fn from(ch: char) -> Self {
         Error::cause(Info::Item(ch))
     }
 }
 
-impl<S: Stream> From<&'static str> for Error<S> {
+impl<S: TokenStream> From<&'static str> for Error<S> {
     fn from(s: &'static str) -> Self {
         Error::cause(Info::Msg(s))
     }
 }
 
-impl<S: Stream> From<String> for Error<S> {
+impl<S: TokenStream> From<String> for Error<S> {
     fn from(s: String) -> Self {
         Error::cause(Info::MsgOwned(s))
     }
 }
 
-impl<S: Stream, E: StdError + Send + Sync + 'static> From<Box<E>> for Error<S> {
+impl<S: TokenStream, E: StdError + Send + Sync + 'static> From<Box<E>> for Error<S> {
     fn from(error: Box<E>) -> Self {
-        error.to_string().into()
+        Error::cause(Info::Custom(Arc::from(error as Box<dyn Payload>)))
     }
 }
 
 // --------------------------------------------------------------------
 // enum Expected
 
+/// Describes the input that a parser expected.
+///
+/// `Expected` is displayed in natural language, e.g. `'a' or a digit`,
+/// `one of 'a', "bc" or a digit` and `'a' followed by a digit`.
 #[derive(Debug, Clone)]
-pub enum Expected<S: Stream> {
+pub enum Expected<S: TokenStream> {
     Seq(Vec<Expected<S>>),
     OneOf(Vec<Expected<S>>),
     Info(Info<S>),
 }
 
-impl<S: Stream> Expected<S> {
+impl<S: TokenStream> Expected<S> {
     pub fn item(item: S::Item) -> Self {
         Expected::Info(Info::Item(item))
     }
@@ -195,58 +312,162 @@ impl<S: Stream> Expected<S> {
         Self::merge_errors(errors, Expected::OneOf)
     }
 
+    /// Returns a machine-readable representation of the expected input. [`Info`]s are represented
+    /// by [`Info::to_json`], while `Seq` and `OneOf` are represented as
+    /// `{ "kind": "sequence" | "one_of", "items": [...] }`.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Expected::Seq(errors) => json!({
+                "kind": "sequence",
+                "items": errors.iter().map(Expected::to_json).collect::<Vec<_>>(),
+            }),
+            Expected::OneOf(errors) => json!({
+                "kind": "one_of",
+                "items": errors.iter().map(Expected::to_json).collect::<Vec<_>>(),
+            }),
+            Expected::Info(info) => info.to_json(),
+        }
+    }
+
+    /// Returns the normalized form of `self`:
+    ///
+    /// * nested `OneOf`s are flattened into their parent, as are nested `Seq`s,
+    /// * duplicate alternatives are removed from `OneOf`s,
+    /// * alternatives are sorted: items first, then ranges, messages, end-of-input and sequences,
+    ///   each in order of their `Display` output,
+    /// * `OneOf`s and `Seq`s with a single element are replaced by that element.
+    pub fn normalize(self) -> Self {
+        match self {
+            Expected::Seq(errors) => {
+                let mut vec = Vec::with_capacity(errors.len());
+                for e in errors {
+                    match e.normalize() {
+                        Expected::Seq(xs) => vec.extend(xs),
+                        e => vec.push(e),
+                    }
+                }
+                Self::unwrap_single(vec, Expected::Seq)
+            }
+            Expected::OneOf(errors) => {
+                let mut vec: Vec<Expected<S>> = Vec::with_capacity(errors.len());
+                for e in errors {
+                    let alternatives = match e.normalize() {
+                        Expected::OneOf(xs) => xs,
+                        e => vec![e],
+                    };
+                    for x in alternatives {
+                        if !vec.contains(&x) {
+                            vec.push(x);
+                        }
+                    }
+                }
+                vec.sort_by_cached_key(Expected::sort_key);
+                Self::unwrap_single(vec, Expected::OneOf)
+            }
+            info => info,
+        }
+    }
+
+    fn sort_key(&self) -> (u8, String) {
+        let rank = match self {
+            Expected::Info(Info::Item(_)) => 0,
+            Expected::Info(Info::Range(_)) => 1,
+            Expected::Info(Info::EOI) | Expected::Info(Info::Incomplete) => 3,
+            Expected::Info(_) => 2,
+            Expected::Seq(_) => 4,
+            Expected::OneOf(_) => 5,
+        };
+        (rank, self.to_string())
+    }
+
+    fn unwrap_single<F>(mut vec: Vec<Expected<S>>, f: F) -> Self
+    where
+        F: FnOnce(Vec<Expected<S>>) -> Expected<S>,
+    {
+        if vec.len() == 1 {
+            vec.pop().unwrap()
+        } else {
+            f(vec)
+        }
+    }
+
     fn merge_errors<I, F>(errors: I, f: F) -> Option<Expected<S>>
     where
         I: IntoIterator<Item = Option<Expected<S>>>,
         F: FnOnce(Vec<Expected<S>>) -> Expected<S>,
     {
-        let mut vec = errors.into_iter().fold(Vec::new(), |mut acc, e| {
-            match e {
-                Some(Expected::OneOf(xs)) => acc.extend(xs.into_iter()),
-                Some(expected) => acc.push(expected),
-                _ => (),
-            };
-            acc
-        });
-        match vec.len() {
-            0 => None,
-            1 => vec.pop(),
-            _ => Some(f(vec)),
+        let vec: Vec<_> = errors.into_iter().flatten().collect();
+        if vec.is_empty() {
+            None
+        } else {
+            Some(f(vec).normalize())
         }
     }
 }
 
-impl<S: Stream> PartialEq for Expected<S> {
+impl<S: TokenStream> PartialEq for Expected<S> {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Expected::Seq(l), Expected::Seq(r)) => l == r,
-            (Expected::OneOf(l), Expected::OneOf(r)) => l == r,
+            // Alternatives are unordered.
+            (Expected::OneOf(l), Expected::OneOf(r)) => {
+                l.iter().all(|e| r.contains(e)) && r.iter().all(|e| l.contains(e))
+            }
             (Expected::Info(l), Expected::Info(r)) => l == r,
             _ => false,
         }
     }
 }
 
-impl<S: Stream> Eq for Expected<S> {}
+impl<S: TokenStream> Eq for Expected<S> {}
 
-impl<S: Stream> fmt::Display for Expected<S> {
+impl<S: TokenStream> fmt::Display for Expected<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fn join_errors<S: Stream>(errors: &[Expected<S>], sep: &str) -> String {
-            errors
-                .into_iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<String>>()
-                .join(sep)
+        // Nested sequences and alternatives are parenthesized to keep them unambiguous.
+        fn fmt_nested<S: TokenStream>(e: &Expected<S>, f: &mut fmt::Formatter) -> fmt::Result {
+            match e {
+                Expected::Info(_) => write!(f, "{}", e),
+                _ => write!(f, "({})", e),
+            }
         }
         match self {
-            Self::Seq(errors) => write!(f, "({})", join_errors(errors, ", ")),
-            Self::OneOf(errors) => write!(f, "({})", join_errors(errors, " OR ")),
+            Self::Seq(errors) => {
+                for (i, e) in errors.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" followed by ")?;
+                    }
+                    fmt_nested(e, f)?;
+                }
+                Ok(())
+            }
+            Self::OneOf(errors) => {
+                if errors.len() > 2 {
+                    f.write_str("one of ")?;
+                }
+                for (i, e) in errors.iter().enumerate() {
+                    if i + 1 == errors.len() && i > 0 {
+                        f.write_str(" or ")?;
+                    } else if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt_nested(e, f)?;
+                }
+                Ok(())
+            }
+            Self::Info(Info::Item(item)) => match item.to_char() {
+                Some(c) => write!(f, "{:?}", c),
+                None => write!(f, "{:?}", item),
+            },
+            Self::Info(Info::Range(range)) => match Token::to_text(range.tokens()) {
+                Some(s) => write!(f, "{:?}", s),
+                None => write!(f, "{:?}", range),
+            },
             Self::Info(info) => write!(f, "{}", info),
         }
     }
 }
 
-impl<S: Stream, T: Into<Expected<S>>> From<Vec<T>> for Expected<S> {
+impl<S: TokenStream, T: Into<Expected<S>>> From<Vec<T>> for Expected<S> {
     fn from(errors: Vec<T>) -> Self {
         Expected::Seq(
             errors
@@ -257,7 +478,7 @@ impl<S: Stream, T: Into<Expected<S>>> From<Vec<T>> for Expected<S> {
     }
 }
 
-impl<S: Stream, T: Into<Expected<S>>> From<HashSet<T>> for Expected<S> {
+impl<S: TokenStream, T: Into<Expected<S>>> From<HashSet<T>> for Expected<S> {
     fn from(errors: HashSet<T>) -> Self {
         Expected::OneOf(
             errors
@@ -268,7 +489,7 @@ impl<S: Stream, T: Into<Expected<S>>> From<HashSet<T>> for Expected<S> {
     }
 }
 
-impl<S: Stream> From<Info<S>> for Expected<S> {
+impl<S: TokenStream> From<Info<S>> for Expected<S> {
     fn from(info: Info<S>) -> Self {
         Expected::Info(info)
     }
@@ -282,32 +503,51 @@ impl<S: Stream> From<u8> for Expected<S> {
 
 impl<S> From<char> for Expected<S>
 where
-    S: Stream<Item = char>,
+    S: TokenStream<Item = char>,
 {
     fn from(ch: char) -> Self {
         Expected::Info(Info::Item(ch))
     }
 }
 
-impl<S: Stream> From<&'static str> for Expected<S> {
+impl<S: TokenStream> From<&'static str> for Expected<S> {
     fn from(s: &'static str) -> Self {
         Expected::Info(Info::Msg(s))
     }
 }
 
-impl<S: Stream> From<String> for Expected<S> {
+impl<S: TokenStream> From<String> for Expected<S> {
     fn from(s: String) -> Self {
         Expected::Info(Info::MsgOwned(s))
     }
 }
 
+// --------------------------------------------------------------------
+// trait Payload
+
+/// A user-defined error that can be carried by an [`Error`], via [`Info::Custom`].
+///
+/// This is implemented for every type that is [`Display`](fmt::Display), [`Debug`](fmt::Debug),
+/// [`Send`], [`Sync`] and `'static`, so domain errors can be returned from parsers and
+/// recovered later with [`Error::downcast_ref`].
+pub trait Payload: Any + fmt::Debug + fmt::Display + Send + Sync {
+    /// Returns `self` as [`Any`], so that it can be downcast to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + fmt::Debug + fmt::Display + Send + Sync> Payload for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 // --------------------------------------------------------------------
 // enum Info
 
 /// Holds the _cause_ of a parse error.
-#[derive(Debug, Clone, Hash)]
-pub enum Info<S: Stream> {
-    /// Error caused by a specific [item](stream::StreamItem) of the parsed input.
+#[derive(Debug, Clone)]
+pub enum Info<S: TokenStream> {
+    /// Error caused by a specific [item](stream::Token) of the parsed input.
     Item(S::Item),
     /// Error caused by a specific [range](stream::RangeStream) of the parsed input.
     Range(S::Range),
@@ -315,46 +555,139 @@ pub enum Info<S: Stream> {
     Msg(&'static str),
     /// Owned version of [`Msg`].
     MsgOwned(String),
+    /// User-defined error, which can be recovered with [`Info::downcast_ref`].
+    Custom(Arc<dyn Payload>),
     /// Parser reached end-of-input too soon.
     EOI,
+    /// Parser reached the end of the input available so far on a
+    /// [partial stream](TokenStream::is_partial), and needs more input to continue.
+    Incomplete,
 }
 
-impl<S: Stream> PartialEq for Info<S> {
+impl<S: TokenStream> Info<S> {
+    /// Create an `Info` holding a user-defined error.
+    pub fn custom<E: Payload>(error: E) -> Self {
+        Info::Custom(Arc::new(error))
+    }
+
+    /// Returns a reference to the user-defined error held by this `Info`, if it is of type `E`.
+    pub fn downcast_ref<E: Payload>(&self) -> Option<&E> {
+        match self {
+            Info::Custom(payload) => (**payload).as_any().downcast_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns a machine-readable representation of the `Info`, as
+    /// `{ "kind": "item" | "range" | "message" | "custom" | "eoi" | "incomplete", "value": ... }`.
+    /// The `value` is the item, range or message as a string, and is omitted for end-of-input and
+    /// incomplete input.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Info::Item(item) => json!({
+                "kind": "item",
+                "value": match item.to_char() {
+                    Some(c) => c.to_string(),
+                    None => format!("{:?}", item),
+                },
+            }),
+            Info::Range(range) => json!({
+                "kind": "range",
+                "value": match Token::to_text(range.tokens()) {
+                    Some(s) => s,
+                    None => match range.tokens().map(|t| t.to_char()).collect() {
+                        Some(s) => s,
+                        None => format!("{:?}", range),
+                    },
+                },
+            }),
+            Info::Msg(msg) => json!({ "kind": "message", "value": msg }),
+            Info::MsgOwned(msg) => json!({ "kind": "message", "value": msg }),
+            Info::Custom(payload) => json!({ "kind": "custom", "value": payload.to_string() }),
+            Info::EOI => json!({ "kind": "eoi" }),
+            Info::Incomplete => json!({ "kind": "incomplete" }),
+        }
+    }
+
+    /// Returns the message held by a [`Msg`], [`MsgOwned`] or [`Custom`] `Info`.
+    ///
+    /// [`Msg`]: Info::Msg
+    /// [`MsgOwned`]: Info::MsgOwned
+    /// [`Custom`]: Info::Custom
+    fn message(&self) -> Option<Cow<'_, str>> {
+        match self {
+            Info::Msg(msg) => Some(Cow::Borrowed(msg)),
+            Info::MsgOwned(msg) => Some(Cow::Borrowed(msg)),
+            Info::Custom(payload) => Some(Cow::Owned(payload.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl<S: TokenStream> PartialEq for Info<S> {
     fn eq(&self, other: &Info<S>) -> bool {
         match (self, other) {
-            (&Info::Item(ref l), &Info::Item(ref r)) => l == r,
-            (&Info::Range(ref l), &Info::Range(ref r)) => l == r,
-            (&Info::Msg(ref l), &Info::Msg(ref r)) => l == r,
-            (&Info::MsgOwned(ref l), &Info::MsgOwned(ref r)) => l == r,
-            (&Info::Msg(ref l), &Info::MsgOwned(ref r)) => l == r,
-            (&Info::MsgOwned(ref l), &Info::Msg(ref r)) => l == r,
-            (&Info::EOI, &Info::EOI) => true,
-            _ => false,
+            (Info::Item(l), Info::Item(r)) => l == r,
+            (Info::Range(l), Info::Range(r)) => l == r,
+            (Info::EOI, Info::EOI) => true,
+            (Info::Incomplete, Info::Incomplete) => true,
+            // User-defined errors are equal if they're of the same type and have the same text.
+            (Info::Custom(l), Info::Custom(r)) => {
+                (**l).as_any().type_id() == (**r).as_any().type_id()
+                    && l.to_string() == r.to_string()
+            }
+            (Info::Custom(_), _) | (_, Info::Custom(_)) => false,
+            // Messages are compared by their text.
+            (l, r) => match (l.message(), r.message()) {
+                (Some(l), Some(r)) => l == r,
+                _ => false,
+            },
         }
     }
 }
 
-impl<S: Stream> Eq for Info<S> {}
+impl<S: TokenStream> Eq for Info<S> {}
 
-impl<S: Stream> fmt::Display for Info<S> {
+impl<S: TokenStream> Hash for Info<S>
+where
+    S::Item: Hash,
+    S::Range: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Info::Item(item) => (0, item).hash(state),
+            Info::Range(range) => (1, range).hash(state),
+            Info::EOI => 3.hash(state),
+            Info::Incomplete => 4.hash(state),
+            info => (2, info.message()).hash(state),
+        }
+    }
+}
+
+impl<S: TokenStream> fmt::Display for Info<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Info::Item(item) => write!(f, "item '{}'", item.as_char()),
+            Info::Item(item) => match item.to_char() {
+                Some(c) => write!(f, "item '{}'", c),
+                None => write!(f, "item {:?}", item),
+            },
             Info::Range(range) => write!(f, "range {:?}", range),
             Info::Msg(msg) => write!(f, "{}", msg),
             Info::MsgOwned(msg) => write!(f, "{}", msg),
+            Info::Custom(payload) => write!(f, "{}", payload),
             Info::EOI => write!(f, "end of input"),
+            Info::Incomplete => write!(f, "end of available input"),
         }
     }
 }
 
-impl<S: Stream> From<&'static str> for Info<S> {
+impl<S: TokenStream> From<&'static str> for Info<S> {
     fn from(s: &'static str) -> Self {
         Info::Msg(s)
     }
 }
 
-impl<S: Stream> From<String> for Info<S> {
+impl<S: TokenStream> From<String> for Info<S> {
     fn from(s: String) -> Self {
         Info::MsgOwned(s)
     }
@@ -362,7 +695,7 @@ impl<S: Stream> From<String> for Info<S> {
 
 impl<S> From<char> for Info<S>
 where
-    S: Stream<Item = char>,
+    S: TokenStream<Item = char>,
 {
     fn from(c: char) -> Self {
         Info::Item(c)
@@ -371,9 +704,135 @@ where
 
 impl<S> From<u8> for Info<S>
 where
-    S: Stream<Item = u8>,
+    S: TokenStream<Item = u8>,
 {
     fn from(b: u8) -> Self {
         Info::Item(b)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::Parser;
+    use stream::{IndexedStream, SourceCode};
+
+    #[test]
+    fn test_to_json() {
+        let mut parser = range("key: ")
+            .with(item(b'y').or(ascii::digit()))
+            .context("entry");
+        let (error, _) = parser.parse(SourceCode::from("key: x")).unwrap_err();
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "message": error.to_string(),
+                "position": { "line": 1, "column": 6 },
+                "span": null,
+                "cause": { "kind": "item", "value": "x" },
+                "expected": [
+                    { "kind": "item", "value": "y" },
+                    { "kind": "message", "value": "an ascii digit" },
+                ],
+                "context": ["entry"],
+                "hints": [],
+            })
+        );
+
+        let mut parser = range("abc");
+        let (error, _) = parser.parse(IndexedStream::from(&b"abx"[..])).unwrap_err();
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "message": error.to_string(),
+                "position": { "offset": 2 },
+                "span": { "start": { "offset": 0 }, "end": { "offset": 3 } },
+                "cause": { "kind": "item", "value": "x" },
+                "expected": [{ "kind": "range", "value": "abc" }],
+                "context": [],
+                "hints": [],
+            })
+        );
+
+        let (error, _) = ascii::digit().parse("").unwrap_err();
+        assert_eq!(
+            error.to_json(),
+            json!({
+                "message": "parsing failed: unexpected end of input: expected an ascii digit",
+                "position": null,
+                "span": null,
+                "cause": { "kind": "eoi" },
+                "expected": [{ "kind": "message", "value": "an ascii digit" }],
+                "context": [],
+                "hints": [],
+            })
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let digit = || Expected::<&str>::from("a digit");
+        let expected = Expected::OneOf(vec![
+            digit(),
+            Expected::OneOf(vec![digit(), '-'.into()]),
+            Expected::OneOf(vec![digit()]),
+        ]);
+        assert_eq!(
+            format!("{:?}", expected.clone().normalize()),
+            format!("{:?}", Expected::OneOf(vec!['-'.into(), digit()]))
+        );
+        assert_eq!(expected.normalize().to_string(), "'-' or a digit");
+
+        let expected = Expected::<&str>::Seq(vec![
+            'a'.into(),
+            Expected::Seq(vec![Expected::OneOf(vec!['b'.into()]), 'c'.into()]),
+        ]);
+        assert_eq!(
+            format!("{:?}", expected.normalize()),
+            format!(
+                "{:?}",
+                Expected::<&str>::Seq(vec!['a'.into(), 'b'.into(), 'c'.into()])
+            )
+        );
+
+        let error = Error::<&str>::eoi().expected_one_of(vec![
+            Expected::from(Info::EOI),
+            Expected::range("ab"),
+            Expected::Seq(vec!['x'.into(), 'y'.into()]),
+            "a digit".into(),
+            'z'.into(),
+            'a'.into(),
+        ]);
+        assert_eq!(
+            error.to_string(),
+            "parsing failed: unexpected end of input: expected one of 'a', 'z', \"ab\", a digit, \
+             end of input or ('x' followed by 'y')"
+        );
+    }
+
+    #[test]
+    fn test_expected_to_json() {
+        let expected: Expected<&str> = Expected::OneOf(vec![
+            Expected::Seq(vec!['a'.into(), "a digit".into()]),
+            Expected::range("bc"),
+        ]);
+        assert_eq!(
+            expected.to_json(),
+            json!({
+                "kind": "one_of",
+                "items": [
+                    {
+                        "kind": "sequence",
+                        "items": [
+                            { "kind": "item", "value": "a" },
+                            { "kind": "message", "value": "a digit" },
+                        ],
+                    },
+                    { "kind": "range", "value": "bc" },
+                ],
+            })
+        );
+    }
+}
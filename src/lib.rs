@@ -1,8 +1,15 @@
+extern crate regex;
+#[macro_use]
+extern crate serde_json;
+
 pub mod error;
+#[macro_use]
 pub mod parser;
+pub mod report;
+pub mod source_map;
 pub mod stream;
 pub mod traits;
 
 pub use error::{Error, Expected, ParseResult};
 pub use parser::Parser;
-pub use stream::Stream;
+pub use stream::{Stream, TokenStream};
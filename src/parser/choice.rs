@@ -1,5 +1,7 @@
-use crate::error::Expected;
-use {ParseResult, Parser, Stream};
+use std::cmp::Ordering;
+
+use crate::error::{Error, Expected};
+use {ParseResult, Parser, TokenStream};
 
 pub struct Skip<P1, P2> {
     p1: P1,
@@ -8,7 +10,7 @@ pub struct Skip<P1, P2> {
 
 impl<S, P1, P2> Parser for Skip<P1, P2>
 where
-    S: Stream,
+    S: TokenStream,
     P1: Parser<Stream = S>,
     P2: Parser<Stream = S>,
 {
@@ -40,7 +42,7 @@ pub struct With<P1, P2> {
 
 impl<S, P1, P2> Parser for With<P1, P2>
 where
-    S: Stream,
+    S: TokenStream,
     P1: Parser<Stream = S>,
     P2: Parser<Stream = S>,
 {
@@ -81,6 +83,8 @@ where
         let mut stream = match self.p.parse_lazy(stream) {
             Ok((Some(result), stream)) => return stream.ok(result),
             Ok((None, stream)) => stream,
+            // With more input, `p` might succeed.
+            Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
             Err((_, stream)) => stream,
         };
         stream.restore(backup);
@@ -90,6 +94,10 @@ where
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.p.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
 }
 
 /// Wrap `p` so that if it would fail it returns `None` instead. Equivalent to
@@ -126,6 +134,10 @@ where
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.p.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
 }
 
 pub fn must<P: Parser>(p: P) -> Must<P> {
@@ -137,7 +149,7 @@ pub struct Or<L, R> {
     p2: R,
 }
 
-impl<S: Stream, O, L, R> Parser for Or<L, R>
+impl<S: TokenStream, O, L, R> Parser for Or<L, R>
 where
     L: Parser<Stream = S, Output = O>,
     R: Parser<Stream = S, Output = O>,
@@ -146,21 +158,76 @@ where
     type Output = O;
 
     fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
-        match self.p1.try_parse_lazy(stream) {
+        let start = stream.backup();
+        let (mut left, left_stream) = match self.p1.parse_lazy(stream) {
+            Ok((result, stream)) => return stream.result(result),
+            Err(err) => err,
+        };
+        let mut stream = left_stream.backup();
+        stream.restore(start);
+        match self.p2.parse_lazy(stream) {
             Ok((result, stream)) => stream.result(result),
-            Err((_, stream)) => self.p2.parse_lazy(stream),
+            // If both branches fail, report the one that got furthest into the input, along with
+            // its own expected errors and the stream where it failed. Expected errors are only
+            // merged if both failed at the same position.
+            Err((mut right, right_stream)) => match left.position.cmp(&right.position) {
+                Ordering::Greater => {
+                    self.p1.add_expected_error(&mut left);
+                    Err((left, left_stream))
+                }
+                Ordering::Less => {
+                    self.p2.add_expected_error(&mut right);
+                    Err((right, right_stream))
+                }
+                Ordering::Equal => {
+                    // A branch that got past the start of the input already has its expected
+                    // errors, so those of the other branch must be merged in now.
+                    let merged = if left.expected.is_some() || right.expected.is_some() {
+                        self.p1.add_expected_error(&mut left);
+                        self.p2.add_expected_error(&mut right);
+                        Some(Expected::merge_one_of(vec![
+                            left.expected.take(),
+                            right.expected.take(),
+                        ]))
+                    } else {
+                        None
+                    };
+                    // Don't lose track of whether more input could make the left branch succeed.
+                    let (mut error, stream) = if left.is_incomplete() && !right.is_incomplete() {
+                        (left, left_stream)
+                    } else {
+                        (right, right_stream)
+                    };
+                    if let Some(expected) = merged {
+                        error.expected = expected;
+                    }
+                    Err((error, stream))
+                }
+            },
         }
     }
 
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         Expected::merge_one_of(vec![self.p1.expected_error(), self.p2.expected_error()])
     }
+
+    /// Adds the expected errors of both branches, unless the error is from the branch that got
+    /// furthest, which already has its own.
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        if error.expected.is_none() {
+            if let Some(expected) = self.expected_error() {
+                error.add_expected(expected);
+            }
+        }
+    }
 }
 
 /// Equivalent to [`p1.or(p2)`].
 ///
+/// If both parsers fail, the error from the parser that got furthest into the input is returned.
+///
 /// [`p1.or(p2)`]: Parser::or
-pub fn or<S: Stream, O, L, R>(p1: L, p2: R) -> Or<L, R>
+pub fn or<S: TokenStream, O, L, R>(p1: L, p2: R) -> Or<L, R>
 where
     L: Parser<Stream = S, Output = O>,
     R: Parser<Stream = S, Output = O>,
@@ -283,7 +350,7 @@ mod test {
             "feel" => ok("feel", ("", 4)),
             "feet" => ok("feet", ("", 4)),
             "fees" => ok("fee", ("s", 3)),
-            "fern" => err(Error::item('r').at(2).expected_one_of(vec![
+            "fern" => err(Error::item('r').at(2).spanning((0, 3)).expected_one_of(vec![
                 Info::Range("feel"),
                 Info::Range("feet"),
                 Info::Range("fee"),
@@ -291,6 +358,65 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_or_furthest() {
+        let mut parser = or(range("foo"), range("fab"));
+        test_parser!(IndexedStream<&str> => &str | parser, {
+            "fox" => err(Error::item('x').at(2).spanning((0, 3)).expected_range("foo")),
+            "fan" => err(Error::item('n').at(2).spanning((0, 3)).expected_range("fab")),
+            "bar" => err(
+                Error::item('b')
+                    .at(0)
+                    .spanning((0, 1))
+                    .expected_one_of(vec![Info::Range("foo"), Info::Range("fab")])
+            ),
+        });
+
+        let mut parser = or(range("foo"), range("fab")).map(|s: &str| s.len());
+        test_parser!(IndexedStream<&str> => usize | parser, {
+            "fox" => err(Error::item('x').at(2).spanning((0, 3)).expected_range("foo")),
+        });
+
+        // The expected errors of the parser don't depend on the last parse.
+        let mut parser = or(range("foo"), range("fab"));
+        assert!(parser.parse(IndexedStream::from("fox")).is_err());
+        assert_eq!(
+            parser.expected_error(),
+            Expected::merge_one_of(vec![
+                Some(Expected::range("foo")),
+                Some(Expected::range("fab")),
+            ])
+        );
+
+        let mut parser = or(range("foo"), range("fab")).and(item(b'!'));
+        test_parser!(IndexedStream<&str> => (&str, char) | parser, {
+            "bar" => err(
+                Error::item('b')
+                    .at(0)
+                    .spanning((0, 1))
+                    .expected(Expected::Seq(vec![
+                        Expected::OneOf(vec![
+                            Expected::range("foo"),
+                            Expected::range("fab"),
+                        ]),
+                        Expected::item('!'),
+                    ]))
+            ),
+        });
+
+        // The error is returned with the stream where the branch that got furthest failed.
+        let mut parser = or(item(b'a').and(item(b'b')), item(b'c').map(|c| (c, c)));
+        assert_eq!(
+            parser.parse_lazy(IndexedStream::from("ax")).unwrap_err().1,
+            IndexedStream::from(("x", 1))
+        );
+        let mut parser = or(item(b'c').map(|c| (c, c)), item(b'a').and(item(b'b')));
+        assert_eq!(
+            parser.parse_lazy(IndexedStream::from("ax")).unwrap_err().1,
+            IndexedStream::from(("x", 1))
+        );
+    }
+
     #[test]
     fn test_choice() {
         assert_eq!(
@@ -329,5 +455,15 @@ mod test {
             "123a bc" => ok("123".to_string(), ("a bc", 3)),
             "a b c" => ok("a ".to_string(), ("b c", 2)),
         });
+
+        let mut parser = choice![range("GET"), range("GIVE"), range("PUT"), range("GIFT")];
+        test_parser!(IndexedStream<&str> => &str | parser, {
+            "GIT" => err(
+                Error::item('T')
+                    .at(2)
+                    .spanning((0, 3))
+                    .expected_one_of(vec![Info::Range("GIVE"), Info::Range("GIFT")])
+            ),
+        });
     }
 }
@@ -1,12 +1,13 @@
 //! Parsers that transform other Parsers.
 
-use std::fmt::Display;
 use std::iter::FromIterator;
 use std::marker::PhantomData;
 use std::option::Option::*;
 use std::str;
 
-use crate::{Expected, ParseResult, Parser, Stream};
+use crate::error::{Info, Payload};
+use crate::{Error, Expected, ParseResult, Parser, TokenStream};
+use stream::{RecoverStream, Span, Spanned, Token};
 use traits::StrLike;
 
 pub struct Expect<P: Parser> {
@@ -25,6 +26,10 @@ impl<P: Parser> Parser for Expect<P> {
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.expected.clone()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        error.expected = self.expected.clone();
+    }
 }
 
 /// Equivalent to [`parser.expect(error)`](Parser::expect).
@@ -50,6 +55,138 @@ where
     }
 }
 
+pub struct Context<P: Parser> {
+    parser: P,
+    label: Info<P::Stream>,
+}
+
+impl<P: Parser> Parser for Context<P> {
+    type Stream = P::Stream;
+    type Output = P::Output;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        self.parser
+            .parse_lazy(stream)
+            .map_err(|(error, stream)| (error.context(self.label.clone()), stream))
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.parser.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.parser.add_expected_error(error);
+    }
+}
+
+/// Equivalent to [`parser.context(label)`](Parser::context).
+pub fn context<P, I>(parser: P, label: I) -> Context<P>
+where
+    P: Parser,
+    I: Into<Info<P::Stream>>,
+{
+    Context {
+        parser,
+        label: label.into(),
+    }
+}
+
+pub struct Suggest<P: Parser> {
+    parser: P,
+}
+
+impl<P: Parser> Suggest<P> {
+    /// Adds a hint to `error` suggesting the expected literal closest to the word at the start of
+    /// `stream`, if any is close enough to be a likely typo.
+    fn add_suggestion(&self, error: Error<P::Stream>, stream: &P::Stream) -> Error<P::Stream> {
+        let word: Vec<char> = stream
+            .tokens()
+            .map_while(|t| {
+                t.to_char()
+                    .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+            })
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        if word.is_empty() {
+            return error;
+        }
+
+        let mut literals = Vec::new();
+        let expected =
+            Expected::merge_one_of(vec![error.expected.clone(), self.parser.expected_error()]);
+        match expected {
+            Some(Expected::OneOf(errors)) => literals.extend(errors),
+            Some(expected) => literals.push(expected),
+            None => (),
+        }
+
+        let suggestion = literals
+            .iter()
+            .filter_map(|e| match e {
+                Expected::Info(Info::Range(range)) => Token::to_text(range.tokens()),
+                _ => None,
+            })
+            .map(|literal| {
+                let chars: Vec<char> = literal.chars().map(|c| c.to_ascii_lowercase()).collect();
+                (edit_distance(&word, &chars), literal)
+            })
+            .filter(|(distance, literal)| {
+                *distance <= (literal.chars().count().max(word.len()) / 3).max(1)
+            })
+            .min_by_key(|(distance, _)| *distance);
+        match suggestion {
+            Some((_, literal)) => error.hint(format!("did you mean `{}`?", literal)),
+            None => error,
+        }
+    }
+}
+
+impl<P: Parser> Parser for Suggest<P> {
+    type Stream = P::Stream;
+    type Output = P::Output;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let start = stream.backup();
+        match self.parser.parse_lazy(stream) {
+            Err((error, stream)) => Err((self.add_suggestion(error, &start), stream)),
+            result => result,
+        }
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.parser.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.parser.add_expected_error(error);
+    }
+}
+
+/// Equivalent to [`parser.suggest()`](Parser::suggest).
+pub fn suggest<P: Parser>(parser: P) -> Suggest<P> {
+    Suggest { parser }
+}
+
+/// Returns the optimal string alignment distance between `a` and `b`: the number of insertions,
+/// deletions, substitutions and transpositions of adjacent characters needed to turn `a` into `b`.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut d: Vec<Vec<usize>> = (0..=a.len())
+        .map(|i| (0..=b.len()).map(|j| if i == 0 { j } else { i }).collect())
+        .collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
 pub struct Attempt<P: Parser> {
     p: P,
 }
@@ -74,6 +211,10 @@ impl<P: Parser> Parser for Attempt<P> {
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.p.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
 }
 
 pub fn attempt<P: Parser>(p: P) -> Attempt<P> {
@@ -109,6 +250,179 @@ pub fn lookahead<P: Parser>(p: P) -> Lookahead<P> {
     Lookahead { p }
 }
 
+pub struct Recognize<P: Parser> {
+    p: P,
+}
+
+impl<P: Parser> Parser for Recognize<P> {
+    type Stream = P::Stream;
+    type Output = <P::Stream as TokenStream>::Range;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let mut start = stream.backup();
+        let (_, stream) = self.p.parse_lazy(stream)?;
+        let range = start.range(start.distance(&stream)).unwrap();
+        stream.ok(range)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.p.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
+}
+
+/// Equivalent to [`p.recognize()`].
+///
+/// [`p.recognize()`]: Parser::recognize
+pub fn recognize<P: Parser>(p: P) -> Recognize<P> {
+    Recognize { p }
+}
+
+pub struct WithRecognized<P: Parser> {
+    p: P,
+}
+
+impl<P: Parser> Parser for WithRecognized<P> {
+    type Stream = P::Stream;
+    type Output = (P::Output, <P::Stream as TokenStream>::Range);
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let mut start = stream.backup();
+        let (result, stream) = self.p.parse_lazy(stream)?;
+        let range = start.range(start.distance(&stream)).unwrap();
+        stream.result(result.map(|output| (output, range)))
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.p.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
+}
+
+/// Equivalent to [`p.with_recognized()`].
+///
+/// [`p.with_recognized()`]: Parser::with_recognized
+pub fn with_recognized<P: Parser>(p: P) -> WithRecognized<P> {
+    WithRecognized { p }
+}
+
+pub struct WithSpan<P: Parser> {
+    p: P,
+}
+
+impl<P: Parser> Parser for WithSpan<P> {
+    type Stream = P::Stream;
+    type Output = (P::Output, Span<<P::Stream as TokenStream>::Position>);
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let start = stream.position().clone();
+        let (result, stream) = self.p.parse_lazy(stream)?;
+        let span = Span::new(start, stream.position().clone());
+        stream.result(result.map(|output| (output, span)))
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.p.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
+}
+
+/// Equivalent to [`p.with_span()`].
+///
+/// [`p.with_span()`]: Parser::with_span
+pub fn with_span<P: Parser>(p: P) -> WithSpan<P> {
+    WithSpan { p }
+}
+
+type PositionOf<P> = <<P as Parser>::Stream as TokenStream>::Position;
+
+pub type ToSpanned<P> = Map<
+    WithSpan<P>,
+    fn(
+        (<P as Parser>::Output, Span<PositionOf<P>>),
+    ) -> Spanned<<P as Parser>::Output, PositionOf<P>>,
+>;
+
+/// Equivalent to [`p.spanned()`].
+///
+/// [`p.spanned()`]: Parser::spanned
+pub fn spanned<P: Parser>(p: P) -> ToSpanned<P> {
+    with_span(p).map(|(value, span)| Spanned { value, span })
+}
+
+pub struct RecoverWith<P, R> {
+    parser: P,
+    sync: R,
+}
+
+impl<P, R> Parser for RecoverWith<P, R>
+where
+    P: Parser,
+    P::Stream: RecoverStream,
+    R: Parser<Stream = P::Stream>,
+{
+    type Stream = P::Stream;
+    type Output = P::Output;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let start = stream.backup();
+        let (error, mut stream) = match self.parser.parse_lazy(stream) {
+            Err(failure) => failure,
+            ok => return ok,
+        };
+
+        // Skip input until the synchronization point or end-of-input.
+        stream = loop {
+            stream = match self.sync.try_parse_lazy(stream) {
+                Ok((_, stream)) => break stream,
+                Err((_, mut stream)) => {
+                    if stream.pop().is_none() {
+                        break stream;
+                    }
+                    stream
+                }
+            };
+        };
+
+        // Recovering at the end of input would skip nothing, and leave nothing to parse.
+        if stream.peek().is_none() && start.distance(&stream) == 0 {
+            return Err((error, stream));
+        }
+
+        let mut error = error;
+        self.parser.add_expected_error(&mut error);
+        stream.record_error(error);
+        stream.noop()
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.parser.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.parser.add_expected_error(error);
+    }
+}
+
+/// Equivalent to [`parser.recover_with(sync)`](Parser::recover_with).
+pub fn recover_with<P, R>(parser: P, sync: R) -> RecoverWith<P, R>
+where
+    P: Parser,
+    P::Stream: RecoverStream,
+    R: Parser<Stream = P::Stream>,
+{
+    RecoverWith { parser, sync }
+}
+
 pub struct Map<P, F> {
     parser: P,
     f: F,
@@ -130,6 +444,10 @@ where
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.parser.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.parser.add_expected_error(error);
+    }
 }
 
 /// Equivalent to [`parser.map(f)`].
@@ -210,9 +528,19 @@ where
     type Output = O;
 
     fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let start = stream.position().clone();
         let (result, stream) = self.parser.parse_lazy(stream)?;
         match result {
-            Some(value) => (self.f)(value, stream),
+            Some(value) => match (self.f)(value, stream) {
+                Err((error, stream)) => {
+                    let error = match error.span {
+                        Some(_) => error,
+                        None => error.spanning((start, stream.position().clone())),
+                    };
+                    Err((error, stream))
+                }
+                result => result,
+            },
             None => stream.noop(),
         }
     }
@@ -220,6 +548,10 @@ where
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.parser.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.parser.add_expected_error(error);
+    }
 }
 
 /// Equivalent to [`p.and_then()`].
@@ -243,21 +575,25 @@ where
     P: Parser,
     P::Output: StrLike,
     O: str::FromStr,
-    O::Err: Display,
+    O::Err: Payload,
 {
     type Stream = P::Stream;
     type Output = O;
 
     fn parse_partial(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let start = stream.position().clone();
         match self.parser.parse_partial(stream)? {
             (Some(s), stream) => {
-                let result = s
+                let result: Result<O, Error<Self::Stream>> = s
                     .from_utf8()
                     .map_err(|_| "invalid UTF-8".into())
-                    .and_then(|s: &str| s.parse::<O>().map_err(|e: O::Err| e.to_string().into()));
+                    .and_then(|s: &str| s.parse::<O>().map_err(Error::custom));
                 match result {
                     Ok(output) => stream.ok(output),
-                    Err(err) => stream.err(err),
+                    Err(err) => {
+                        let end = stream.position().clone();
+                        stream.err(err.spanning((start, end)))
+                    }
                 }
             }
             (None, stream) => stream.noop(),
@@ -273,7 +609,7 @@ where
     P: Parser,
     P::Output: StrLike,
     O: str::FromStr,
-    O::Err: Display,
+    O::Err: Payload,
 {
     FromStr {
         parser,
@@ -288,11 +624,212 @@ mod test {
     use parser::{
         item::{ascii, item},
         range::range,
-        repeat::many1,
+        repeat::{many, many1, sep_by},
         seq::then,
         test_utils::*,
     };
-    use stream::{IndexedStream, SourceCode};
+    use std::fmt;
+    use std::num::{ParseFloatError, ParseIntError};
+    use stream::{IndexPosition, IndexedStream, SourceCode, SourcePosition, State, Stream};
+
+    #[test]
+    fn test_context() {
+        let mut parser = (
+            range("key: "),
+            many1::<String, _>(ascii::digit()).context("value"),
+        )
+            .context("entry");
+        test_parser!(IndexedStream<&str> => (&str, String) | parser, {
+            "key: 12" => ok(("key: ", "12".to_string()), ("", 7)),
+            "key: x" => err(
+                Error::item('x')
+                    .expected("an ascii digit")
+                    .at(5)
+                    .context("value")
+                    .context("entry")
+            ),
+            "kex" => err(
+                Error::item('x')
+                    .expected_range("key: ")
+                    .at(2)
+                    .spanning((0, 3))
+                    .context("entry")
+            ),
+        });
+
+        let (error, _) = parser.parse(IndexedStream::from("key: x")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "parsing failed at index 5: unexpected item 'x': expected an ascii digit \
+             while parsing value, while parsing entry"
+        );
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        let distance = |a: &str, b: &str| {
+            edit_distance(
+                &a.chars().collect::<Vec<_>>(),
+                &b.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("POST", "POST"), 0);
+        assert_eq!(distance("", "PUT"), 3);
+        assert_eq!(distance("POTS", "POST"), 1);
+        assert_eq!(distance("GTE", "GET"), 1);
+        assert_eq!(distance("PUTT", "PUT"), 1);
+        assert_eq!(distance("HAED", "HEAD"), 1);
+        assert_eq!(distance("CA", "ABC"), 3);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let mut parser = choice![range("let"), range("const"), range("static")].suggest();
+        let keywords = || {
+            vec![
+                Info::Range("let"),
+                Info::Range("const"),
+                Info::Range("static"),
+            ]
+        };
+        test_parser!(IndexedStream<&str> => &str | parser, {
+            "let x" => ok("let", (" x", 3)),
+            "cnost x" => err(
+                Error::item('n')
+                    .expected_range("const")
+                    .at(1)
+                    .spanning((0, 2))
+                    .hint("did you mean `const`?")
+            ),
+            "STATIC x" => err(
+                Error::item('S')
+                    .expected_one_of(keywords())
+                    .at(0)
+                    .spanning((0, 1))
+                    .hint("did you mean `static`?")
+            ),
+            "var x" => err(
+                Error::item('v')
+                    .expected_one_of(keywords())
+                    .at(0)
+                    .spanning((0, 1))
+            ),
+            " let" => err(
+                Error::item(' ')
+                    .expected_one_of(keywords())
+                    .at(0)
+                    .spanning((0, 1))
+            ),
+        });
+
+        // Lengths are counted in characters, so a non-ASCII literal isn't held to a looser limit.
+        let mut parser = range("se\u{f1}or").suggest();
+        test_parser!(IndexedStream<&str> => &str | parser, {
+            "senor" => err(
+                Error::item('n')
+                    .expected_range("se\u{f1}or")
+                    .at(2)
+                    .spanning((0, 3))
+                    .hint("did you mean `se\u{f1}or`?")
+            ),
+            "sxnor" => err(
+                Error::item('x')
+                    .expected_range("se\u{f1}or")
+                    .at(1)
+                    .spanning((0, 2))
+            ),
+        });
+    }
+
+    #[test]
+    fn test_recover_with() {
+        fn statement<S: RecoverStream + Stream<Char = char>>(
+        ) -> impl Parser<Stream = S, Output = String> {
+            many1(ascii::letter())
+                .skip(item(b';'))
+                .recover_with(item(b';'))
+        }
+
+        let mut parser = many::<Vec<_>, _>(statement::<IndexedStream<&str>>());
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("foo;b4r;baz;")),
+            (
+                Some(vec!["foo".to_string(), "baz".to_string()]),
+                vec![Error::item('4').expected_item(';').at(5)]
+            )
+        );
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("foo;b4r")),
+            (
+                Some(vec!["foo".to_string()]),
+                vec![Error::item('4').expected_item(';').at(5)]
+            )
+        );
+
+        // Raw input is wrapped in a stream that records errors.
+        let mut parser = many::<Vec<_>, _>(statement::<IndexedStream<&str>>());
+        assert_eq!(
+            parser.parse_recover("foo;b4r;baz;"),
+            (
+                Some(vec!["foo".to_string(), "baz".to_string()]),
+                vec![Error::item('4').expected_item(';').at(5)]
+            )
+        );
+
+        // Errors recorded by a branch that backtracks are dropped.
+        let mut parser = attempt(statement::<IndexedStream<&str>>().skip(item(b'!')))
+            .or(range("a1;x").map(String::from));
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("a1;x")),
+            (Some("a1;x".to_string()), vec![])
+        );
+
+        // Errors that aren't recovered from are returned last.
+        let mut parser = many::<Vec<_>, _>(statement::<IndexedStream<&str>>()).skip(item(b'.'));
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("?;a1;b")),
+            (
+                None,
+                vec![
+                    Error::item('?').expected("an ascii letter").at(0),
+                    Error::item('1').expected_item(';').at(3),
+                    Error::eoi().expected_item(';').at(6),
+                    Error::eoi().expected_item('.').at(6),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_recover_with_sep_by() {
+        let mut parser = sep_by::<Vec<_>, _, _>(
+            many1::<String, _>(ascii::digit::<IndexedStream<&str>>())
+                .from_str::<u32>()
+                .recover_with(lookahead(item(b','))),
+            item(b','),
+        );
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("1,x2,3")),
+            (
+                Some(vec![1, 3]),
+                vec![Error::item('x').expected("an ascii digit").at(2)]
+            )
+        );
+
+        // Recovery needn't consume input, as long as the separator does.
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("1,,3,")),
+            (
+                Some(vec![1, 3]),
+                vec![Error::item(',').expected("an ascii digit").at(2)]
+            )
+        );
+        assert_eq!(
+            parser.parse_recover(IndexedStream::from("1,")),
+            (Some(vec![1]), vec![])
+        );
+    }
 
     #[test]
     fn test_attempt() {
@@ -311,6 +848,59 @@ mod test {
         assert_eq!(stream, ("abcde!!!", 0).into());
     }
 
+    #[test]
+    fn test_recognize() {
+        fn identifier<S: Stream>() -> impl Parser<Stream = S, Output = S::Range> {
+            ascii::letter()
+                .and(many::<Vec<_>, _>(ascii::alpha_num().or(item(b'_'))))
+                .recognize()
+        }
+        test_parser!(IndexedStream<&str> => &str | identifier(), {
+            "foo_1 = 2" => ok("foo_1", (" = 2", 5)),
+        });
+        test_parser!(State<&[u8], SourcePosition> => &[u8] | identifier(), {
+            &b"x\n"[..] => ok(&b"x"[..], (&b"\n"[..], (1, 1, 2))),
+        });
+
+        let mut parser = many1::<Vec<_>, _>(ascii::digit()).with_recognized();
+        test_parser!(IndexedStream<&str> => (Vec<char>, &str) | parser, {
+            "12a" => ok((vec!['1', '2'], "12"), ("a", 2)),
+            "a" => err(Error::item('a').at(0).expected("an ascii digit")),
+        });
+
+        // Ranges are recognized even if the parser has no output.
+        let mut parser = ascii::digit().map(|_| ()).optional().recognize();
+        test_parser!(&str => &str | parser, {
+            "1a" => ok("1", "a"),
+            "a" => ok("", "a"),
+        });
+    }
+
+    #[test]
+    fn test_spanned() {
+        let mut parser = many1::<String, _>(ascii::digit()).with_span();
+        test_parser!(IndexedStream<&str> => (String, Span<IndexPosition>) | parser, {
+            "123a" => ok(("123".to_string(), Span::from((0, 3))), ("a", 3)),
+            "a" => err(Error::item('a').at(0).expected("an ascii digit")),
+        });
+
+        let word = || many1::<String, _>(ascii::letter()).spanned();
+        let mut parser = sep_by::<Vec<_>, _, _>(word(), many1::<String, _>(ascii::whitespace()));
+        let (result, _) = parser
+            .parse(State::<&str, SourcePosition>::from("let\n  x"))
+            .unwrap();
+        assert_eq!(
+            result,
+            Some(vec![
+                Spanned::new("let".to_string(), ((0, 1, 1), (3, 1, 4))),
+                Spanned::new("x".to_string(), ((6, 2, 3), (7, 2, 4))),
+            ])
+        );
+        let x = &result.unwrap()[1];
+        assert_eq!(x.len(), 1);
+        assert_eq!(x.to_string(), "x at line 2, col 3 to line 2, col 4");
+    }
+
     #[test]
     fn test_map() {
         let mut parser = map(ascii::digit(), |c: char| c.to_string());
@@ -362,13 +952,58 @@ mod test {
             // TODO: add ability to control consumption, e.g. make this error show at beginning (0)
             // TODO: e.g.: many1(alpha_num()).and_then(...).try()
             "324dogs" => err(
-                Error::from("invalid digit found in string")
+                Error::from(Box::new("324dogs".parse::<usize>().unwrap_err()))
                     .expected("an ascii letter or digit")
                     .at(7)
+                    .spanning((0, 7))
             ),
         });
     }
 
+    #[test]
+    fn test_custom_error() {
+        #[derive(Debug, PartialEq)]
+        struct Reserved(String);
+
+        impl fmt::Display for Reserved {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{} is reserved", self.0)
+            }
+        }
+
+        let mut parser = many1::<String, _>(ascii::letter()).and_then(
+            |s: String, stream: IndexedStream<&str>| match s.as_str() {
+                "fn" | "let" => stream.err(Error::custom(Reserved(s))),
+                _ => stream.ok(s),
+            },
+        );
+        test_parser!(IndexedStream<&str> => String | parser, {
+            "x = 1" => ok("x".to_string(), (" = 1", 1)),
+            "let = 1" => err(
+                Error::custom(Reserved("let".to_string()))
+                    .expected("an ascii letter")
+                    .at(3)
+                    .spanning((0, 3))
+            ),
+        });
+
+        let (error, _) = parser.parse(IndexedStream::from("fn")).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<Reserved>(),
+            Some(&Reserved("fn".to_string()))
+        );
+        assert_eq!(error.downcast_ref::<String>(), None);
+        assert_eq!(
+            error.to_string(),
+            "parsing failed at index 2: unexpected fn is reserved: expected an ascii letter"
+        );
+
+        // Custom errors aren't equal to messages or other types with the same text.
+        let error: Error<&str> = Error::custom(Reserved("fn".to_string()));
+        assert_ne!(error, Error::from("fn is reserved"));
+        assert_ne!(error, Error::custom(String::from("fn is reserved")));
+    }
+
     #[test]
     fn test_collect() {
         let mut parser = collect(many1::<Vec<_>, _>(ascii::digit()));
@@ -419,13 +1054,30 @@ mod test {
             "12e" => ok(12 as f32, "e"),
             "-12e" => ok(-12 as f32, "e"),
             "-12.5e" => ok(-12.5 as f32, "e"),
-            "12.5.9" =>  err(Error::from("invalid float literal")),
+            "12.5.9" =>  err(Error::custom("12.5.9".parse::<f32>().unwrap_err())),
         });
 
+        let (error, _) = parser.parse("1.2.3").unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ParseFloatError>(),
+            Some(&"1.2.3".parse::<f32>().unwrap_err())
+        );
+        assert_eq!(error.downcast_ref::<ParseIntError>(), None);
+
         let mut parser = many1::<String, _>(ascii::digit()).from_str::<f32>();
         test_parser!(SourceCode => f32 | parser, {
             "12e" => ok(12f32, ("e", (1, 3))),
             "e12" => err(Error::item('e').expected("an ascii digit").at((1, 1))),
         });
+
+        let mut parser = many1::<String, _>(ascii::digit()).from_str::<u8>();
+        test_parser!(SourceCode => u8 | parser, {
+            "255" => ok(255u8, ("", (1, 4))),
+            "256 " => err(
+                Error::custom("256".parse::<u8>().unwrap_err())
+                    .at((1, 4))
+                    .spanning(((1, 1), (1, 4)))
+            ),
+        });
     }
 }
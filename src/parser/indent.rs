@@ -0,0 +1,383 @@
+//! Parsers for indentation-sensitive languages, which use the offside rule to delimit blocks.
+//!
+//! These parsers read the column of the stream's position, so they require a position that
+//! tracks columns, a [`ColumnPosition`] such as [`LinePosition`](::stream::LinePosition). The
+//! columns of the enclosing blocks are kept in an [`IndentStack`] in the stream's
+//! [user state](StateStream).
+//!
+//! Parsers used within an [`indented_block`] should consume the whitespace and line breaks that
+//! follow them, so that each parser ends at the start of the next line's content.
+
+use std::marker::PhantomData;
+
+use error::{Error, Expected, ParseResult};
+use parser::Parser;
+use stream::{ColumnPosition, StateStream, TokenStream};
+
+/// IndentStack holds the columns of the enclosing indented blocks, innermost last.
+///
+/// It can be used as the user state of a [`State`](::stream::State), or held in a larger user
+/// state that implements `AsRef<IndentStack>` and `AsMut<IndentStack>`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct IndentStack {
+    columns: Vec<u32>,
+}
+
+impl IndentStack {
+    pub fn new() -> Self {
+        IndentStack::default()
+    }
+
+    /// Returns the column of the innermost block, or `None` at the top level.
+    pub fn current(&self) -> Option<u32> {
+        self.columns.last().cloned()
+    }
+
+    /// Returns the number of enclosing blocks.
+    pub fn depth(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn push(&mut self, column: u32) {
+        self.columns.push(column);
+    }
+
+    pub fn pop(&mut self) -> Option<u32> {
+        self.columns.pop()
+    }
+}
+
+impl AsRef<IndentStack> for IndentStack {
+    fn as_ref(&self) -> &IndentStack {
+        self
+    }
+}
+
+impl AsMut<IndentStack> for IndentStack {
+    fn as_mut(&mut self) -> &mut IndentStack {
+        self
+    }
+}
+
+fn column<S>(stream: &S) -> u32
+where
+    S: TokenStream,
+    S::Position: ColumnPosition<S::Stream>,
+{
+    stream.position().column()
+}
+
+fn expected_column<S: TokenStream>(column: u32) -> Expected<S> {
+    format!("indentation to column {}", column).into()
+}
+
+fn current_indent<S>(stream: &S) -> Option<u32>
+where
+    S: StateStream,
+    S::State: AsRef<IndentStack>,
+{
+    stream.state().as_ref().current()
+}
+
+pub struct AtColumn<S> {
+    column: u32,
+    _marker: PhantomData<S>,
+}
+
+impl<S> Parser for AtColumn<S>
+where
+    S: TokenStream,
+    S::Position: ColumnPosition<S::Stream>,
+{
+    type Stream = S;
+    type Output = ();
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        if column(&stream) == self.column {
+            stream.ok(())
+        } else {
+            let error = stream.new_error().expected(expected_column(self.column));
+            stream.err(error)
+        }
+    }
+}
+
+/// Succeeds if the stream is at `column`, without consuming any input.
+pub fn at_column<S>(column: u32) -> AtColumn<S>
+where
+    S: TokenStream,
+    S::Position: ColumnPosition<S::Stream>,
+{
+    AtColumn {
+        column,
+        _marker: PhantomData,
+    }
+}
+
+pub struct SameIndent<S>(PhantomData<S>);
+
+impl<S> Parser for SameIndent<S>
+where
+    S: StateStream,
+    S::Position: ColumnPosition<S::Stream>,
+    S::State: AsRef<IndentStack>,
+{
+    type Stream = S;
+    type Output = ();
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let indent = current_indent(&stream).unwrap_or(1);
+        if column(&stream) == indent {
+            stream.ok(())
+        } else {
+            let error = stream.new_error().expected(expected_column(indent));
+            stream.err(error)
+        }
+    }
+}
+
+/// Succeeds if the stream is at the column of the innermost indented block, or the first column
+/// at the top level, without consuming any input.
+pub fn same_indent<S>() -> SameIndent<S>
+where
+    S: StateStream,
+    S::Position: ColumnPosition<S::Stream>,
+    S::State: AsRef<IndentStack>,
+{
+    SameIndent(PhantomData)
+}
+
+pub struct Indented<S>(PhantomData<S>);
+
+impl<S> Parser for Indented<S>
+where
+    S: StateStream,
+    S::Position: ColumnPosition<S::Stream>,
+    S::State: AsRef<IndentStack>,
+{
+    type Stream = S;
+    type Output = ();
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        match current_indent(&stream) {
+            Some(indent) if column(&stream) <= indent => {
+                let error = stream
+                    .new_error()
+                    .expected(format!("indentation beyond column {}", indent));
+                stream.err(error)
+            }
+            _ => stream.ok(()),
+        }
+    }
+}
+
+/// Succeeds if the stream is indented beyond the column of the innermost indented block, without
+/// consuming any input. Always succeeds at the top level.
+pub fn indented<S>() -> Indented<S>
+where
+    S: StateStream,
+    S::Position: ColumnPosition<S::Stream>,
+    S::State: AsRef<IndentStack>,
+{
+    Indented(PhantomData)
+}
+
+pub struct IndentedBlock<O, P> {
+    p: P,
+    _marker: PhantomData<O>,
+}
+
+impl<O, P> Parser for IndentedBlock<O, P>
+where
+    P: Parser,
+    P::Stream: StateStream,
+    <P::Stream as TokenStream>::Position: ColumnPosition<<P::Stream as TokenStream>::Stream>,
+    <P::Stream as StateStream>::State: AsRef<IndentStack> + AsMut<IndentStack>,
+    O: Extend<P::Output> + Default,
+{
+    type Stream = P::Stream;
+    type Output = O;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let (_, mut stream) = indented().parse_lazy(stream)?;
+        let block = column(&stream);
+        stream.state_mut().as_mut().push(block);
+
+        let mut output = O::default();
+        let mut first = true;
+        loop {
+            let start = stream.backup();
+            let result = match self.p.parse_lazy(stream) {
+                Ok((result, next)) => {
+                    stream = next;
+                    result
+                }
+                Err((error, mut stream)) => {
+                    stream.state_mut().as_mut().pop();
+                    return Err((error, stream));
+                }
+            };
+            // `p` would go on succeeding without consuming any input forever, so the block ends
+            // before an element that consumes nothing.
+            if !first && start.distance(&stream) == 0 {
+                break;
+            }
+            output.extend(result);
+            first = false;
+
+            // The block ends at the end of input, or at a line indented less than the block.
+            if stream.peek().is_none() || column(&stream) < block {
+                break;
+            }
+            if column(&stream) > block {
+                let error = stream.new_error().expected(expected_column(block));
+                stream.state_mut().as_mut().pop();
+                return stream.err(error);
+            }
+        }
+
+        stream.state_mut().as_mut().pop();
+        stream.ok(output)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.p.expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
+}
+
+/// Parses a block of one or more `p`, which must each start at the same column, indented beyond
+/// the enclosing block. The block ends at a line indented less than its first line, or at the
+/// end of input, and fails at a line indented further.
+///
+/// For example, with `p` parsing a name followed by an optional nested block, `indented_block(p)`
+/// parses:
+///
+/// ```text
+/// a
+///   b
+///     c
+///   d
+/// e
+/// ```
+pub fn indented_block<O, P>(p: P) -> IndentedBlock<O, P>
+where
+    P: Parser,
+    P::Stream: StateStream,
+    <P::Stream as TokenStream>::Position: ColumnPosition<<P::Stream as TokenStream>::Stream>,
+    <P::Stream as StateStream>::State: AsRef<IndentStack> + AsMut<IndentStack>,
+    O: Extend<P::Output> + Default,
+{
+    IndentedBlock {
+        p,
+        _marker: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::{Error, ParseResult};
+    use parser::choice::optional;
+    use parser::item::{ascii, item};
+    use parser::repeat::{many, many1};
+    use stream::{LinePosition, State};
+
+    type Source = State<&'static str, LinePosition, IndentStack>;
+
+    #[derive(Debug, PartialEq)]
+    struct Node {
+        name: String,
+        children: Vec<Node>,
+    }
+
+    fn node(name: &str, children: Vec<Node>) -> Node {
+        Node {
+            name: name.to_string(),
+            children,
+        }
+    }
+
+    /// Parses a name followed by an optional block of children.
+    fn tree(stream: Source) -> ParseResult<Source, Node> {
+        let (name, stream) = many1::<String, _>(ascii::letter())
+            .skip(many::<Vec<_>, _>(ascii::whitespace()))
+            .parse_lazy(stream)?;
+        let (children, stream) = optional(indented_block(tree as fn(_) -> _)).parse_lazy(stream)?;
+        stream.ok(Node {
+            name: name.unwrap(),
+            children: children.unwrap_or_default(),
+        })
+    }
+
+    #[test]
+    fn test_indented_block() {
+        let mut parser = indented_block::<Vec<_>, _>(tree as fn(_) -> _);
+        test_parser!(Source => Vec<Node> | parser, {
+            "a\n  b\n    c\n  d\ne\n" => ok(
+                vec![
+                    node("a", vec![node("b", vec![node("c", vec![])]), node("d", vec![])]),
+                    node("e", vec![]),
+                ],
+                ("", (6, 1))
+            ),
+            "a\n  b\n c" => err(
+                Error::item('c')
+                    .expected("indentation to column 1")
+                    .at((3, 2))
+            ),
+        });
+
+        let mut parser = indented_block::<Vec<_>, _>(tree as fn(_) -> _);
+        test_parser!(Source => Vec<Node> | parser, {
+            // A dedent can close several nested blocks at once.
+            "a\n  b\n    c\n      d\ne" => ok(
+                vec![
+                    node("a", vec![node("b", vec![node("c", vec![node("d", vec![])])])]),
+                    node("e", vec![]),
+                ],
+                ("", (5, 2))
+            ),
+            // A dedent must return to the column of an enclosing block.
+            "a\n    b\n  c" => err(
+                Error::item('c')
+                    .expected("indentation to column 1")
+                    .at((3, 3))
+            ),
+        });
+
+        // The block ends if an element consumes nothing.
+        let mut parser = indented_block::<Vec<_>, _>(
+            many::<String, _>(ascii::letter()).skip(many::<Vec<_>, _>(ascii::whitespace())),
+        );
+        test_parser!(Source => Vec<String> | parser, {
+            "ab\n!" => ok(vec!["ab".to_string()], ("!", (2, 1))),
+        });
+    }
+
+    #[test]
+    fn test_indentation() {
+        let mut parser = item(b' ').with(at_column(2)).with(same_indent());
+        test_parser!(Source => () | parser, {
+            " x" => err(Error::item('x').expected("indentation to column 1").at((1, 2))),
+        });
+
+        let mut parser = item(b' ').with(at_column(3));
+        test_parser!(Source => () | parser, {
+            " x" => err(Error::item('x').expected("indentation to column 3").at((1, 2))),
+        });
+
+        let mut parser = indented::<Source>();
+        let mut stream = Source::with_state(" x", (1, 2), IndentStack::new());
+        stream.state_mut().push(2);
+        assert_eq!(
+            parser.parse(stream).unwrap_err().0,
+            Error::item(' ')
+                .expected("indentation beyond column 2")
+                .at((1, 2))
+        );
+    }
+}
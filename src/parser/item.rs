@@ -5,11 +5,11 @@ use std::marker::PhantomData;
 use error::{Error, Expected, Info, ParseResult};
 use parser::combinator::Expect;
 use parser::Parser;
-use stream::{Position, Stream, StreamItem};
+use stream::{Position, Stream, StreamItem, TokenStream};
 
-pub struct Any<S: Stream>(PhantomData<S>);
+pub struct Any<S: TokenStream>(PhantomData<S>);
 
-impl<S: Stream> Parser for Any<S> {
+impl<S: TokenStream> Parser for Any<S> {
     type Stream = S;
     type Output = S::Item;
 
@@ -17,7 +17,10 @@ impl<S: Stream> Parser for Any<S> {
         let start = stream.position().clone();
         match stream.pop() {
             Some(t) => stream.ok(t),
-            None => stream.err_at(start, Error::eoi()),
+            None => {
+                let error = stream.end_of_input();
+                stream.err_at(start, error)
+            }
         }
     }
 
@@ -26,15 +29,15 @@ impl<S: Stream> Parser for Any<S> {
     }
 }
 
-pub fn any<S: Stream>() -> Any<S> {
+pub fn any<S: TokenStream>() -> Any<S> {
     Any(PhantomData)
 }
 
-pub struct Item<S: Stream> {
+pub struct Item<S: TokenStream> {
     item: S::Item,
 }
 
-impl<S: Stream> Parser for Item<S>
+impl<S: TokenStream> Parser for Item<S>
 where
     S::Item: PartialEq,
 {
@@ -47,10 +50,13 @@ where
                 stream.pop();
                 stream.ok(item)
             }
-            result => stream.err(match result {
-                Some(item) => Error::item(item),
-                None => Error::eoi(),
-            }),
+            result => {
+                let error = match result {
+                    Some(item) => Error::item(item),
+                    None => stream.end_of_input(),
+                };
+                stream.err(error)
+            }
         }
     }
 
@@ -65,15 +71,23 @@ pub fn item<S: Stream>(item: u8) -> Item<S> {
     }
 }
 
+/// Parses `token`. Like [`item`], but for any [`TokenStream`], such as a
+/// [`TokenSlice`](::stream::TokenSlice) of a lexer's output.
+pub fn token<S: TokenStream>(token: S::Item) -> Item<S> {
+    Item { item: token }
+}
+
 pub struct EOI<O, S>(PhantomData<(O, S)>);
 
-impl<S: Stream, O> Parser for EOI<O, S> {
+impl<S: TokenStream, O> Parser for EOI<O, S> {
     type Stream = S;
     type Output = O;
 
     fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
         match stream.peek() {
             Some(t) => stream.err(Error::item(t)),
+            // More input might follow on a partial stream.
+            None if stream.is_partial() => stream.err(Error::incomplete()),
             None => stream.noop(),
         }
     }
@@ -83,15 +97,15 @@ impl<S: Stream, O> Parser for EOI<O, S> {
     }
 }
 
-pub fn eoi<O, S: Stream>() -> EOI<O, S> {
+pub fn eoi<O, S: TokenStream>() -> EOI<O, S> {
     EOI(PhantomData)
 }
 
-pub fn eoi_<S: Stream>() -> EOI<(), S> {
+pub fn eoi_<S: TokenStream>() -> EOI<(), S> {
     EOI(PhantomData)
 }
 
-pub struct Satisfy<S: Stream, F>
+pub struct Satisfy<S: TokenStream, F>
 where
     F: Fn(&S::Item) -> bool,
 {
@@ -99,7 +113,7 @@ where
     _marker: PhantomData<S>,
 }
 
-impl<S: Stream, F> Parser for Satisfy<S, F>
+impl<S: TokenStream, F> Parser for Satisfy<S, F>
 where
     F: Fn(&S::Item) -> bool,
 {
@@ -113,12 +127,15 @@ where
                 stream.ok(*t)
             }
             Some(t) => stream.err(Error::item(t)),
-            _ => stream.err(Error::eoi()),
+            _ => {
+                let error = stream.end_of_input();
+                stream.err(error)
+            }
         }
     }
 }
 
-pub fn satisfy<S: Stream, F>(f: F) -> Satisfy<S, F>
+pub fn satisfy<S: TokenStream, F>(f: F) -> Satisfy<S, F>
 where
     F: Fn(&S::Item) -> bool,
 {
@@ -133,7 +150,7 @@ pub struct Negate<P: Parser> {
 }
 impl<S, P> Parser for Negate<P>
 where
-    S: Stream,
+    S: TokenStream,
     P: Parser<Stream = S, Output = S::Item>,
 {
     type Stream = S;
@@ -151,24 +168,27 @@ where
         stream.restore(initial);
         match stream.pop() {
             Some(t) => stream.ok(t),
-            None => stream.err(Error::eoi()),
+            None => {
+                let error = stream.end_of_input();
+                stream.err(error)
+            }
         }
     }
 }
 
 pub fn negate<S, P>(p: P) -> Negate<P>
 where
-    S: Stream,
+    S: TokenStream,
     P: Parser<Stream = S, Output = S::Item>,
 {
     Negate { p }
 }
 
-pub struct OneOf<S: Stream> {
+pub struct OneOf<S: TokenStream> {
     items: Vec<S::Item>,
 }
 
-impl<S: Stream> Parser for OneOf<S> {
+impl<S: TokenStream> Parser for OneOf<S> {
     type Stream = S;
     type Output = S::Item;
 
@@ -196,11 +216,22 @@ where
     }
 }
 
-pub struct NoneOf<S: Stream> {
+/// Parses any of `tokens`. Like [`one_of`], but for any [`TokenStream`].
+pub fn one_of_tokens<S, I>(tokens: I) -> OneOf<S>
+where
+    S: TokenStream,
+    I: IntoIterator<Item = S::Item>,
+{
+    OneOf {
+        items: tokens.into_iter().collect(),
+    }
+}
+
+pub struct NoneOf<S: TokenStream> {
     items: Vec<S::Item>,
 }
 
-impl<S: Stream> Parser for NoneOf<S> {
+impl<S: TokenStream> Parser for NoneOf<S> {
     type Stream = S;
     type Output = S::Item;
 
@@ -219,6 +250,17 @@ where
     }
 }
 
+/// Parses any token except `tokens`. Like [`none_of`], but for any [`TokenStream`].
+pub fn none_of_tokens<S, I>(tokens: I) -> NoneOf<S>
+where
+    S: TokenStream,
+    I: IntoIterator<Item = S::Item>,
+{
+    NoneOf {
+        items: tokens.into_iter().collect(),
+    }
+}
+
 #[cfg(test)]
 macro_rules! def_token_parser_tests {
     ($( $name:ident ($p:ident, $e:expr) { valid( $($t_ok:expr),+ ); error( $($t_err:expr),+ ); } )+) => {
@@ -376,7 +418,7 @@ pub mod unicode {
             $(#[$attr])*
             pub fn $name<S>() -> Expect<Satisfy<S, fn(&S::Item) -> bool>>
             where
-                S: Stream<Item = char>,
+                S: TokenStream<Item = char>,
                 S::Position: Position<S::Stream>,
             {
                 let f: fn(&S::Item) -> bool = |&c| <char>::$f(c);
@@ -521,6 +563,7 @@ mod test {
             "" => err(Error::eoi().expected_one_of(vec![b'a', b'0'])),
             "z" => err(Error::item('z').expected_one_of(vec![b'a', b'0'])),
         });
+        assert_eq!(one_of(b"a0").parse("0a"), Ok((Some('0'), "a")));
     }
 
     #[test]
@@ -532,5 +575,6 @@ mod test {
             "" => err(Error::eoi()),
             "a" => err(Error::item('a')),
         });
+        assert_eq!(none_of(b"a0").parse("1a"), Ok((Some('1'), "a")));
     }
 }
@@ -8,29 +8,33 @@ mod test_utils;
 #[macro_use]
 pub mod choice;
 pub mod combinator;
+pub mod indent;
 pub mod item;
+pub mod pratt;
 pub mod range;
+pub mod regex;
 pub mod repeat;
 pub mod seq;
+pub mod state;
 
-use std::fmt::Display;
 use std::iter::{self, FromIterator, IntoIterator};
 use std::ops::{Add, BitAnd, BitOr, Mul, Sub};
 use std::str;
 
 use self::choice::{must, optional, or, skip, with, Must, Optional, Or, Skip, With};
 use self::combinator::{
-    and_then, collect, expect, flatten, from_str, map, no_expect, wrap, AndThen, Collect, Expect,
-    Flatten, FromStr, Map, Wrap,
+    and_then, collect, context, expect, flatten, from_str, map, no_expect, recognize, recover_with,
+    spanned, suggest, with_recognized, with_span, wrap, AndThen, Collect, Context, Expect, Flatten,
+    FromStr, Map, Recognize, RecoverWith, Suggest, ToSpanned, WithRecognized, WithSpan, Wrap,
 };
 use self::item::{negate, Negate};
 use self::seq::{and, append, extend, then, And, Append, Extend, Then};
-use error::{Error, Expected, ParseResult};
-use stream::{RangeStream, Stream};
+use error::{Error, Expected, Info, ParseResult, Payload};
+use stream::{RangeStream, RecoverStream, TokenStream};
 use traits::StrLike;
 
 pub trait Parser {
-    type Stream: Stream;
+    type Stream: TokenStream;
     type Output;
 
     /// Parses `stream`. Doesn't revert `stream` or add expected errors if parsing fails.
@@ -66,15 +70,17 @@ pub trait Parser {
         result
     }
 
-    /// Parses `stream`. If parsing fails, reverts `stream` and adds expected errors.
+    /// Parses `stream`. If parsing fails, reverts `stream` and adds expected errors. Streams that
+    /// discard consumed input are only reverted if the start of the input is still available (see
+    /// [`TokenStream::restore_checkpoint`]).
     ///
     /// This is typically used to initiate parsing from the top-level parser. It is provided as the
     /// main entrypoint into parsing.
     fn parse(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
-        let backup = stream.backup();
+        let checkpoint = stream.checkpoint();
         let mut result = self.parse_partial(stream);
         if let Err((_, ref mut stream)) = result {
-            stream.restore(backup);
+            stream.restore_checkpoint(checkpoint);
         }
         result
     }
@@ -85,23 +91,45 @@ pub trait Parser {
         &mut self,
         stream: Self::Stream,
     ) -> Result<(Self::Output, Self::Stream), (Error<Self::Stream>, Self::Stream)> {
-        let backup = stream.backup();
+        let checkpoint = stream.checkpoint();
         match self.parse_partial(stream) {
             Ok((Some(output), stream)) => Ok((output, stream)),
             Ok((None, mut stream)) => {
-                &mut stream.restore(backup);
+                stream.restore_checkpoint(checkpoint);
                 let mut error = stream.new_error();
                 self.add_expected_error(&mut error);
                 Err((error, stream))
             }
             Err((mut error, mut stream)) => {
-                &mut stream.restore(backup);
+                stream.restore_checkpoint(checkpoint);
                 self.add_expected_error(&mut error);
                 Err((error, stream))
             }
         }
     }
 
+    /// Parses `input`, recovering from errors with parsers built by [`Parser::recover_with`].
+    ///
+    /// Returns the output, if any, along with every error encountered: first the errors that were
+    /// recovered from, then the error that stopped parsing, if there was one. Errors are recorded
+    /// on the stream, so it must be a [`RecoverStream`] such as [`State`](::stream::State), which
+    /// `input` is converted into, so raw input such as a `&str` can be passed directly.
+    fn parse_recover<I>(&mut self, input: I) -> (Option<Self::Output>, Vec<Error<Self::Stream>>)
+    where
+        Self: Sized,
+        Self::Stream: RecoverStream,
+        I: Into<Self::Stream>,
+    {
+        match self.parse_partial(input.into()) {
+            Ok((output, mut stream)) => (output, stream.take_errors()),
+            Err((error, mut stream)) => {
+                let mut errors = stream.take_errors();
+                errors.push(error);
+                (None, errors)
+            }
+        }
+    }
+
     /// Returns a [`Vec`] of the expected errors that should be added if parsing fails.
     ///
     /// By default this returns an empty [`Vec`].
@@ -122,7 +150,10 @@ pub trait Parser {
     /// Returns a mutable referance to this parser.
     ///
     /// Equivalent to `&mut p`; `by_ref` removes the need for wrapping parenthesis in some cases.
-    fn by_ref(&mut self) -> &mut Self {
+    fn by_ref(&mut self) -> &mut Self
+    where
+        Self: Sized,
+    {
         self
     }
 
@@ -147,13 +178,53 @@ pub trait Parser {
         no_expect(self)
     }
 
+    /// Label `self` with a description of what it parses. If parsing fails, `label` is pushed onto
+    /// the error's [`context`](Error::context) stack, keeping its cause and expected errors intact.
+    fn context<I>(self, label: I) -> Context<Self>
+    where
+        Self: Sized,
+        I: Into<Info<Self::Stream>>,
+    {
+        context(self, label)
+    }
+
+    /// Suggest corrections for misspelled keywords. If parsing fails, the word at the start of the
+    /// input is compared against the [ranges](Expected::range) that were expected, and a hint such
+    /// as "did you mean `POST`?" is added to the error for the closest one, if it is within a few
+    /// edits. Comparisons ignore ASCII case.
+    ///
+    /// This is typically used with a [`choice!`] of [`range`](range::range) keywords.
+    fn suggest(self) -> Suggest<Self>
+    where
+        Self: Sized,
+    {
+        suggest(self)
+    }
+
+    /// Recover from failures of `self` by recording the error on the stream and skipping input
+    /// until `sync` parses successfully, or until end-of-input. `sync` consumes the input it
+    /// parses; wrap it in [`lookahead`](combinator::lookahead) to leave it for the next parser.
+    ///
+    /// A recovered parse produces no output, so repeating parsers such as
+    /// [`many`](repeat::many) and [`sep_by`](repeat::sep_by) skip the bad element and carry on with
+    /// the rest. At end of input, where there's nothing left to skip, the original error is
+    /// returned instead. Recorded errors are collected by [`Parser::parse_recover`].
+    fn recover_with<R>(self, sync: R) -> RecoverWith<Self, R>
+    where
+        Self: Sized,
+        Self::Stream: RecoverStream,
+        R: Parser<Stream = Self::Stream>,
+    {
+        recover_with(self, sync)
+    }
+
     /// Reverses the parse behavior of `self`. Fails if `self` succeeds, succeeds if `self` fails.
     ///
     /// Only works for item parsers.
     fn negate<S>(self) -> Negate<Self>
     where
         Self: Sized + Parser<Stream = S, Output = S::Item>,
-        S: Stream,
+        S: TokenStream,
     {
         negate(self)
     }
@@ -235,13 +306,68 @@ pub trait Parser {
         and_then(self, f)
     }
 
+    /// Parses with `self`, discarding its output, and returns the range of input it consumed. The
+    /// range is borrowed from the stream, so this is a zero-copy alternative to collecting the
+    /// items of a composite parser into a [`String`].
+    ///
+    /// ```
+    /// # use rparse::Parser;
+    /// # use rparse::parser::repeat::many1;
+    /// # use rparse::parser::item::{ascii::digit, item};
+    /// let mut p = many1::<Vec<_>, _>(digit()).and(item(b'.')).and(many1::<Vec<_>, _>(digit()));
+    /// assert_eq!(p.recognize().parse("3.14!"), Ok((Some("3.14"), "!")));
+    /// ```
+    fn recognize(self) -> Recognize<Self>
+    where
+        Self: Sized,
+    {
+        recognize(self)
+    }
+
+    /// Like [`Parser::recognize`], but returns the output of `self` along with the range of input
+    /// it consumed.
+    fn with_recognized(self) -> WithRecognized<Self>
+    where
+        Self: Sized,
+    {
+        with_recognized(self)
+    }
+
+    /// Parses with `self` and returns its output along with the [`Span`](::stream::Span) of input
+    /// it consumed, from the stream's position before parsing to its position after.
+    fn with_span(self) -> WithSpan<Self>
+    where
+        Self: Sized,
+    {
+        with_span(self)
+    }
+
+    /// Like [`Parser::with_span`], but returns the output and span in a
+    /// [`Spanned`](::stream::Spanned), so that nodes of a syntax tree can be mapped back to the
+    /// source for diagnostics.
+    ///
+    /// The span includes everything `self` consumes, so parsers that skip trailing whitespace
+    /// should do so outside of `spanned`.
+    fn spanned(self) -> ToSpanned<Self>
+    where
+        Self: Sized,
+    {
+        spanned(self)
+    }
+
     /// Parses with `self` and transforms the result using [`str::FromStr`].
+    ///
+    /// If the conversion fails, its error is kept as the cause of the parse error, and can be
+    /// recovered with [`Error::downcast_ref`]. This requires the error to be a [`Payload`], which
+    /// the errors of the standard library's `FromStr` implementations are. Errors that are only
+    /// [`Display`](std::fmt::Display) can be converted with [`and_then`](Parser::and_then)
+    /// instead, reporting them as messages.
     fn from_str<O>(self) -> FromStr<Self, O>
     where
         Self: Sized,
         Self::Output: StrLike,
         O: str::FromStr,
-        O::Err: Display,
+        O::Err: Payload,
     {
         from_str(self)
     }
@@ -256,7 +382,7 @@ pub trait Parser {
     where
         Self: Sized,
         Self: Parser<Stream = S, Output = O>,
-        S: Stream<Range = O>,
+        S: TokenStream<Range = O>,
         O: RangeStream,
     {
         and_then(self, |range, stream| match range.into_string() {
@@ -271,7 +397,7 @@ pub trait Parser {
     where
         Self: Sized,
         Self: Parser<Stream = S, Output = O>,
-        S: Stream<Range = O>,
+        S: TokenStream<Range = O>,
         O: RangeStream,
     {
         self.as_string()
@@ -473,7 +599,32 @@ impl<P: Parser> Parser for &mut P {
     }
 }
 
-impl<'a, S: Stream, O> Parser for dyn FnMut(S) -> ParseResult<S, O> + 'a {
+impl<P: Parser + ?Sized> Parser for Box<P> {
+    type Stream = P::Stream;
+    type Output = P::Output;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        (**self).parse_lazy(stream)
+    }
+
+    fn parse_partial(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        (**self).parse_partial(stream)
+    }
+
+    fn try_parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        (**self).try_parse_lazy(stream)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        (**self).expected_error()
+    }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        (**self).add_expected_error(error);
+    }
+}
+
+impl<'a, S: TokenStream, O> Parser for dyn FnMut(S) -> ParseResult<S, O> + 'a {
     type Stream = S;
     type Output = O;
 
@@ -482,7 +633,7 @@ impl<'a, S: Stream, O> Parser for dyn FnMut(S) -> ParseResult<S, O> + 'a {
     }
 }
 
-impl<S: Stream, O> Parser for fn(S) -> ParseResult<S, O> {
+impl<S: TokenStream, O> Parser for fn(S) -> ParseResult<S, O> {
     type Stream = S;
     type Output = O;
 
@@ -491,7 +642,7 @@ impl<S: Stream, O> Parser for fn(S) -> ParseResult<S, O> {
     }
 }
 
-pub fn parser<S: Stream, O>(f: fn(S) -> ParseResult<S, O>) -> fn(S) -> ParseResult<S, O> {
+pub fn parser<S: TokenStream, O>(f: fn(S) -> ParseResult<S, O>) -> fn(S) -> ParseResult<S, O> {
     f
 }
 
@@ -508,6 +659,10 @@ impl<P: Parser> Parser for Q<P> {
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.0.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.0.add_expected_error(error);
+    }
 }
 
 impl<O, P, P2> Add<P2> for Q<P>
@@ -525,7 +680,7 @@ where
 
 impl<S, P, P2> Sub<P2> for Q<P>
 where
-    S: Stream,
+    S: TokenStream,
     P: Parser<Stream = S>,
     P2: Parser<Stream = S>,
 {
@@ -538,7 +693,7 @@ where
 
 impl<S, P, P2> Mul<P2> for Q<P>
 where
-    S: Stream,
+    S: TokenStream,
     P: Parser<Stream = S>,
     P2: Parser<Stream = S>,
 {
@@ -551,7 +706,7 @@ where
 
 impl<S, O, P, P2> BitOr<P2> for Q<P>
 where
-    S: Stream,
+    S: TokenStream,
     P: Parser<Stream = S, Output = O>,
     P2: Parser<Stream = S, Output = O>,
 {
@@ -564,7 +719,7 @@ where
 
 impl<S, P, P2> BitAnd<P2> for Q<P>
 where
-    S: Stream,
+    S: TokenStream,
     P: Parser<Stream = S>,
     P2: Parser<Stream = S>,
 {
@@ -584,13 +739,13 @@ mod test {
         range::range,
         repeat::{many, many1, many_n_m},
     };
-    use stream::{IndexedStream, Position};
+    use stream::{IndexedStream, Position, Stream};
 
     #[test]
     fn test_parser_from_closure() {
         fn vowel<S>() -> impl Parser<Stream = S, Output = char>
         where
-            S: Stream<Item = char>,
+            S: TokenStream<Item = char>,
             S::Position: Position<S::Stream>,
         {
             parser(|mut stream: S| {
@@ -705,7 +860,7 @@ mod test {
             "feel" => ok("feel", ("", 4)),
             "feet" => ok("feet", ("", 4)),
             "fees" => ok("fee", ("s", 3)),
-            "fern" => err(Error::item('r').at(2).expected_one_of(vec![
+            "fern" => err(Error::item('r').at(2).spanning((0, 3)).expected_one_of(vec![
                 Info::Range("feel"),
                 Info::Range("feet"),
                 Info::Range("fee"),
@@ -0,0 +1,371 @@
+//! An operator-precedence parser for expressions, using Pratt parsing.
+//!
+//! An expression parser is built from a parser for the operands, or atoms, of the expression,
+//! such as numbers, names and parenthesized expressions, and a set of prefix, infix and postfix
+//! operators. Each operator is a parser along with a precedence, and a function that folds the
+//! operator's output and its operands into the output of the expression:
+//!
+//! ```
+//! # extern crate rparse;
+//! # use rparse::Parser;
+//! # use rparse::parser::item::{ascii::digit, item};
+//! # use rparse::parser::pratt::{pratt, Assoc};
+//! # fn main() {
+//! let number = digit().map(|c: char| c.to_digit(10).unwrap() as i64);
+//! let mut expr = pratt(number)
+//!     .infix(1, Assoc::Left, item(b'+'), |l, _, r| l + r)
+//!     .infix(1, Assoc::Left, item(b'-'), |l, _, r| l - r)
+//!     .infix(2, Assoc::Left, item(b'*'), |l, _, r| l * r)
+//!     .infix(4, Assoc::Right, item(b'^'), |l, _, r| l.pow(r as u32))
+//!     .prefix(3, item(b'-'), |_, x: i64| -x);
+//! assert_eq!(expr.parse("1+2*3-4"), Ok((Some(3), "")));
+//! assert_eq!(expr.parse("-2^2^3"), Ok((Some(-256), "")));
+//! # }
+//! ```
+//!
+//! Operators with a higher precedence bind more tightly. Prefix operators bind more tightly than
+//! infix operators of the same precedence, and postfix operators more tightly than either. Atoms
+//! and operators should consume any whitespace that follows them.
+
+use std::rc::Rc;
+
+use error::{Expected, ParseResult};
+use parser::Parser;
+use stream::TokenStream;
+
+/// The associativity of an infix operator, which determines how a chain of operators with the
+/// same precedence is grouped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Assoc {
+    /// `a - b - c` is parsed as `(a - b) - c`.
+    Left,
+    /// `a ^ b ^ c` is parsed as `a ^ (b ^ c)`.
+    Right,
+    /// `a == b == c` is an error.
+    Neither,
+}
+
+/// Returns the binding power of an operator of `precedence`, plus `offset`. Operators of higher
+/// precedence have higher powers whatever the offset, which orders the operators of a single
+/// precedence.
+fn power(precedence: u32, offset: u64) -> u64 {
+    4 * u64::from(precedence) + offset
+}
+
+type Unary<'a, O> = Box<dyn FnOnce(O) -> O + 'a>;
+type Binary<'a, O> = Box<dyn FnOnce(O, O) -> O + 'a>;
+
+/// An operator parser, whose output is the function applying the operator to its operands.
+struct Operator<'a, S, F> {
+    precedence: u32,
+    assoc: Assoc,
+    parser: Box<dyn Parser<Stream = S, Output = F> + 'a>,
+}
+
+/// Parses any of `operators`, returning the precedence and associativity of the one that
+/// matched along with its output. Operators that fail are backtracked, unless the stream is
+/// incomplete.
+fn operator<S: TokenStream, F>(
+    operators: &mut [Operator<S, F>],
+    mut stream: S,
+) -> ParseResult<S, (u32, Assoc, F)> {
+    for operator in operators.iter_mut() {
+        stream = match operator.parser.try_parse_lazy(stream) {
+            Ok((Some(f), stream)) => return stream.ok((operator.precedence, operator.assoc, f)),
+            Ok((None, stream)) => stream,
+            Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
+            Err((_, stream)) => stream,
+        };
+    }
+    stream.noop()
+}
+
+pub struct Pratt<'a, A: Parser> {
+    atom: A,
+    prefix: Vec<Operator<'a, A::Stream, Unary<'a, A::Output>>>,
+    infix: Vec<Operator<'a, A::Stream, Binary<'a, A::Output>>>,
+    postfix: Vec<Operator<'a, A::Stream, Unary<'a, A::Output>>>,
+}
+
+impl<'a, A: Parser> Pratt<'a, A>
+where
+    A::Output: 'a,
+{
+    /// Adds a prefix operator with `precedence`. When `op` parses, its output and the operand
+    /// that follows it are folded with `f`. Chainable.
+    pub fn prefix<P, F>(mut self, precedence: u32, op: P, f: F) -> Self
+    where
+        P: Parser<Stream = A::Stream> + 'a,
+        F: Fn(P::Output, A::Output) -> A::Output + 'a,
+        P::Output: 'a,
+    {
+        let f = Rc::new(f);
+        let parser = op.map(move |op| {
+            let f = f.clone();
+            Box::new(move |x| f(op, x)) as Unary<_>
+        });
+        self.prefix.push(Operator {
+            precedence,
+            assoc: Assoc::Right,
+            parser: Box::new(parser),
+        });
+        self
+    }
+
+    /// Adds an infix operator with `precedence` and `assoc`iativity. When `op` parses, the
+    /// operands on either side of it are folded with its output by `f`. Chainable.
+    pub fn infix<P, F>(mut self, precedence: u32, assoc: Assoc, op: P, f: F) -> Self
+    where
+        P: Parser<Stream = A::Stream> + 'a,
+        F: Fn(A::Output, P::Output, A::Output) -> A::Output + 'a,
+        P::Output: 'a,
+    {
+        let f = Rc::new(f);
+        let parser = op.map(move |op| {
+            let f = f.clone();
+            Box::new(move |l, r| f(l, op, r)) as Binary<_>
+        });
+        self.infix.push(Operator {
+            precedence,
+            assoc,
+            parser: Box::new(parser),
+        });
+        self
+    }
+
+    /// Adds a postfix operator with `precedence`. When `op` parses, the operand that precedes it
+    /// and its output are folded with `f`. Chainable.
+    pub fn postfix<P, F>(mut self, precedence: u32, op: P, f: F) -> Self
+    where
+        P: Parser<Stream = A::Stream> + 'a,
+        F: Fn(A::Output, P::Output) -> A::Output + 'a,
+        P::Output: 'a,
+    {
+        let f = Rc::new(f);
+        let parser = op.map(move |op| {
+            let f = f.clone();
+            Box::new(move |x| f(x, op)) as Unary<_>
+        });
+        self.postfix.push(Operator {
+            precedence,
+            assoc: Assoc::Left,
+            parser: Box::new(parser),
+        });
+        self
+    }
+
+    /// Parses a prefix operator followed by its operand, or an atom.
+    fn operand(&mut self, stream: A::Stream) -> ParseResult<A::Stream, A::Output> {
+        let (prefix, stream) = operator(&mut self.prefix, stream)?;
+        if let Some((precedence, _, f)) = prefix {
+            let (x, stream) = self.expression(stream, power(precedence, 2))?;
+            return stream.result(x.map(f));
+        }
+
+        let start = stream.position().clone();
+        match self.atom.parse_partial(stream) {
+            Ok((Some(x), stream)) => stream.ok(x),
+            Ok((None, stream)) => {
+                let error = stream.new_error().expected("an operand");
+                stream.err(error)
+            }
+            Err((mut error, stream)) => {
+                if error.position == start {
+                    error.expected = Some(Expected::from("an operand"));
+                }
+                Err((error, stream))
+            }
+        }
+    }
+
+    /// Parses an expression whose operators bind at least as tightly as `min_power`.
+    ///
+    /// Infix operators of precedence `p` bind with a power of `4p` on their left, and `4p + 1` on
+    /// their right, except right-associative operators, which bind with `4p + 1` on their left and
+    /// `4p` on their right. Prefix operators bind with `4p + 2` on their right, which is more than
+    /// any infix operator of the same precedence, and postfix operators bind with `4p + 2` on their
+    /// left, so they bind more tightly still.
+    fn expression(
+        &mut self,
+        stream: A::Stream,
+        min_power: u64,
+    ) -> ParseResult<A::Stream, A::Output> {
+        let (lhs, mut stream) = self.operand(stream)?;
+        let mut lhs = match lhs {
+            Some(lhs) => lhs,
+            None => return stream.noop(),
+        };
+        // The precedence of the last non-associative operator applied at this level.
+        let mut non_assoc = None;
+
+        loop {
+            let backup = stream.backup();
+            let (postfix, next) = operator(&mut self.postfix, stream)?;
+            stream = next;
+            if let Some((precedence, _, f)) = postfix {
+                if power(precedence, 2) < min_power {
+                    stream.restore(backup);
+                    break;
+                }
+                lhs = f(lhs);
+                continue;
+            }
+
+            let (infix, next) = operator(&mut self.infix, stream)?;
+            stream = next;
+            let (precedence, assoc, f) = match infix {
+                Some(infix) => infix,
+                None => break,
+            };
+            let (left, right) = match assoc {
+                Assoc::Right => (power(precedence, 1), power(precedence, 0)),
+                Assoc::Left | Assoc::Neither => (power(precedence, 0), power(precedence, 1)),
+            };
+            if left < min_power {
+                stream.restore(backup);
+                break;
+            }
+            if assoc == Assoc::Neither {
+                if non_assoc == Some(precedence) {
+                    stream.restore(backup);
+                    let error = stream
+                        .new_error()
+                        .hint("this operator is non-associative, so chains of it need parentheses");
+                    return stream.err(error);
+                }
+                non_assoc = Some(precedence);
+            }
+
+            let (rhs, next) = self.expression(stream, right)?;
+            stream = next;
+            lhs = match rhs {
+                Some(rhs) => f(lhs, rhs),
+                None => return stream.noop(),
+            };
+        }
+        stream.ok(lhs)
+    }
+}
+
+impl<'a, A: Parser> Parser for Pratt<'a, A>
+where
+    A::Output: 'a,
+{
+    type Stream = A::Stream;
+    type Output = A::Output;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        self.expression(stream, 0)
+    }
+}
+
+/// Create an expression parser with `atom` as the parser for operands, and no operators.
+/// Operators are added with [`Pratt::prefix`], [`Pratt::infix`] and [`Pratt::postfix`].
+///
+/// If an operand is missing, parsing fails with an error that expects "an operand".
+pub fn pratt<'a, A: Parser>(atom: A) -> Pratt<'a, A> {
+    Pratt {
+        atom,
+        prefix: Vec::new(),
+        infix: Vec::new(),
+        postfix: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::seq::between;
+    use stream::{IndexedStream, Stream, StreamItem};
+
+    type Source = IndexedStream<&'static str>;
+
+    /// Parses an expression into a string with its operations parenthesized.
+    fn expr(stream: Source) -> ParseResult<Source, String> {
+        let atom = ascii::letter().map(|c: char| c.to_string()).or(between(
+            item(b'('),
+            item(b')'),
+            expr as fn(_) -> _,
+        ));
+        pratt(atom)
+            .infix(1, Assoc::Neither, range("=="), |l, _, r| {
+                format!("({} == {})", l, r)
+            })
+            .infix(2, Assoc::Left, item(b'+'), |l, _, r| {
+                format!("({} + {})", l, r)
+            })
+            .infix(2, Assoc::Left, item(b'-'), |l, _, r| {
+                format!("({} - {})", l, r)
+            })
+            .infix(3, Assoc::Left, item(b'*'), |l, _, r| {
+                format!("({} * {})", l, r)
+            })
+            .infix(5, Assoc::Right, item(b'^'), |l, _, r| {
+                format!("({} ^ {})", l, r)
+            })
+            .prefix(4, item(b'-'), |_, x| format!("(-{})", x))
+            .postfix(6, item(b'!'), |x, _| format!("({}!)", x))
+            .parse_lazy(stream)
+    }
+
+    fn number<S: Stream>() -> impl Parser<Stream = S, Output = i64> {
+        ascii::digit().map(|c: S::Item| c.as_char().to_digit(10).unwrap() as i64)
+    }
+
+    #[test]
+    fn test_pratt() {
+        let mut parser = expr as fn(_) -> _;
+        test_parser!(Source => String | parser, {
+            "a" => ok("a".to_string(), ("", 1)),
+            "a+b*c-d" => ok("((a + (b * c)) - d)".to_string(), ("", 7)),
+            "a^b^c" => ok("(a ^ (b ^ c))".to_string(), ("", 5)),
+            "-a^b" => ok("(-(a ^ b))".to_string(), ("", 4)),
+            "-a*b" => ok("((-a) * b)".to_string(), ("", 4)),
+            "--a!" => ok("(-(-(a!)))".to_string(), ("", 4)),
+            "a*b!" => ok("(a * (b!))".to_string(), ("", 4)),
+            "(a+b)*c" => ok("((a + b) * c)".to_string(), ("", 7)),
+            "a==b+c" => ok("(a == (b + c))".to_string(), ("", 6)),
+            "a+b c" => ok("(a + b)".to_string(), (" c", 3)),
+            "" => err(Error::eoi().at(0).expected("an operand")),
+            "a+" => err(Error::eoi().at(2).expected("an operand")),
+            "a*-" => err(Error::eoi().at(3).expected("an operand")),
+            "a==b==c" => err(
+                Error::item('=')
+                    .at(4)
+                    .hint("this operator is non-associative, so chains of it need parentheses")
+            ),
+        });
+
+        // Operators of the same precedence: postfix binds tightest, then prefix, then infix.
+        let mut parser = pratt(ascii::letter().map(|c: char| c.to_string()))
+            .infix(3, Assoc::Left, item(b'+'), |l, _, r| {
+                format!("({} + {})", l, r)
+            })
+            .infix(3, Assoc::Right, item(b'^'), |l, _, r| {
+                format!("({} ^ {})", l, r)
+            })
+            .prefix(3, item(b'-'), |_, x| format!("(-{})", x))
+            .postfix(3, item(b'!'), |x, _| format!("({}!)", x));
+        test_parser!(&str => String | parser, {
+            "-a!" => ok("(-(a!))".to_string(), ""),
+            "a+b!" => ok("(a + (b!))".to_string(), ""),
+            "a!+b" => ok("((a!) + b)".to_string(), ""),
+            "-a+b" => ok("((-a) + b)".to_string(), ""),
+            "-a^b" => ok("((-a) ^ b)".to_string(), ""),
+            "a^-b^c" => ok("(a ^ ((-b) ^ c))".to_string(), ""),
+        });
+
+        let mut parser = pratt(number())
+            .infix(1, Assoc::Left, item(b'-'), |l, _, r| l - r)
+            .infix(2, Assoc::Left, item(b'/'), |l, _, r| l / r)
+            .prefix(3, item(b'-'), |_, x| -x);
+        test_parser!(&str => i64 | parser, {
+            "9-4-3" => ok(2, ""),
+            "8/4/2" => ok(1, ""),
+            "-9--4" => ok(-5, ""),
+        });
+    }
+}
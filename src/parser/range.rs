@@ -1,20 +1,22 @@
 //! Parsers that parse a continuous series of tokens.
 
-use error::{Error, Expected, ParseResult};
+use std::marker::PhantomData;
+
+use error::{Error, Expected, Info, ParseResult};
 use parser::Parser;
-use stream::{Position, RangeStream, Stream};
+use stream::{Position, RangeStream, Stream, TextRange, Token, TokenStream};
 
-pub struct Range<S: Stream> {
+pub struct Range<S: TokenStream> {
     range: S::Range,
 }
 
-impl<S: Stream> Parser for Range<S> {
+impl<S: TokenStream> Parser for Range<S> {
     type Stream = S;
     type Output = S::Range;
 
     fn parse_lazy(&mut self, mut stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
         let idx = self.range.len();
-        let mut start_pos = stream.position().clone();
+        let start = stream.position().clone();
 
         let mut range = match stream.range(idx) {
             Some(range) => {
@@ -27,17 +29,29 @@ impl<S: Stream> Parser for Range<S> {
             None => stream.as_range(),
         };
 
-        let first_bad_idx = range
+        // The index of the first mismatch is counted in the units of `TokenStream::range`.
+        let mut idx = 0;
+        let first_bad = range
             .tokens()
             .zip(self.range.tokens())
-            .enumerate()
-            .find(|&(_, (left, right))| left != right);
-        let (range, error) = match first_bad_idx {
-            Some((i, (left, _))) => (range.range(i).unwrap(), Error::item(left)),
-            None => (range.as_range(), Error::eoi()),
+            .find(|&(left, right)| {
+                idx += left.width();
+                left != right
+            });
+        let (range, error) = match first_bad {
+            Some((left, _)) => (range.range(idx - left.width()).unwrap(), Error::item(left)),
+            None => (range.as_range(), stream.end_of_input()),
         };
-        start_pos.update_range(&range);
-        stream.err_at(start_pos, error)
+        let mut position = start.clone();
+        position.update_range(&range);
+        let mut end = position.clone();
+        if let Info::Item(ref item) = error.cause {
+            position.followed_by(Some(item));
+            end.update(item);
+        } else {
+            position.followed_by(None);
+        }
+        stream.err_at(position, error.spanning((start, end)))
     }
 
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
@@ -51,10 +65,121 @@ pub fn range<S: Stream>(range: &'static str) -> Range<S> {
     }
 }
 
+pub struct TakeWhile<S, F> {
+    f: F,
+    till: bool,
+    min: usize,
+    max: Option<usize>,
+    _marker: PhantomData<S>,
+}
+
+impl<S: TokenStream, F> Parser for TakeWhile<S, F>
+where
+    F: FnMut(&S::Item) -> bool,
+{
+    type Stream = S;
+    type Output = S::Range;
+
+    fn parse_lazy(&mut self, mut stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let mut count = 0;
+        let mut idx = 0;
+        let mut rejected = None;
+        for item in stream.tokens() {
+            if self.max == Some(count) {
+                break;
+            }
+            if (self.f)(&item) == self.till {
+                rejected = Some(item);
+                break;
+            }
+            count += 1;
+            idx += item.width();
+        }
+        let range = stream.range(idx).unwrap();
+
+        // At the end of a partial stream, more input might continue the range.
+        let error = match rejected {
+            Some(item) if count < self.min => Error::item(item),
+            None if count < self.min || (stream.is_partial() && self.max != Some(count)) => {
+                stream.end_of_input()
+            }
+            _ => return stream.ok(range),
+        };
+        stream.err(error)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        let tokens = |n| if n == 1 { "token" } else { "tokens" };
+        match (self.min, self.max) {
+            (0, _) => None,
+            (m, Some(n)) if m == n => Some(format!("{} matching {}", n, tokens(n)).into()),
+            (m, Some(n)) => Some(format!("{} to {} matching tokens", m, n).into()),
+            (m, None) => Some(format!("at least {} matching {}", m, tokens(m)).into()),
+        }
+    }
+}
+
+fn take<S: TokenStream, F>(f: F, till: bool, min: usize, max: Option<usize>) -> TakeWhile<S, F>
+where
+    F: FnMut(&S::Item) -> bool,
+{
+    TakeWhile {
+        f,
+        till,
+        min,
+        max,
+        _marker: PhantomData,
+    }
+}
+
+/// Parses the longest range of tokens that satisfy `f`, which may be empty. The range is borrowed
+/// from the stream with [`TokenStream::range`], rather than collected token by token.
+pub fn take_while<S: TokenStream, F>(f: F) -> TakeWhile<S, F>
+where
+    F: FnMut(&S::Item) -> bool,
+{
+    take(f, false, 0, None)
+}
+
+/// Parses the longest range of one or more tokens that satisfy `f`.
+pub fn take_while1<S: TokenStream, F>(f: F) -> TakeWhile<S, F>
+where
+    F: FnMut(&S::Item) -> bool,
+{
+    take(f, false, 1, None)
+}
+
+/// Parses the longest range of at least `m` and at most `n` tokens that satisfy `f`.
+///
+/// # Panics
+///
+/// Panics if `m` is greater than `n`.
+pub fn take_while_m_n<S: TokenStream, F>(m: usize, n: usize, f: F) -> TakeWhile<S, F>
+where
+    F: FnMut(&S::Item) -> bool,
+{
+    assert!(
+        m <= n,
+        "take_while_m_n: m ({}) is greater than n ({})",
+        m,
+        n
+    );
+    take(f, false, m, Some(n))
+}
+
+/// Parses the longest range of tokens that don't satisfy `f`, which may be empty, stopping at the
+/// first token that does.
+pub fn take_till<S: TokenStream, F>(f: F) -> TakeWhile<S, F>
+where
+    F: FnMut(&S::Item) -> bool,
+{
+    take(f, true, 0, None)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use stream::IndexedStream;
+    use stream::{IndexedStream, Partial, SourcePosition, State};
 
     #[test]
     fn test_range() {
@@ -63,12 +188,76 @@ mod test {
             "def" => ok("def", ("", 3)),
             "defcon" => ok("def", ("con", 3)),
             "" => err(Error::eoi().at(0).expected_range("def")),
-            "de" => err(Error::eoi().at(2).expected_range("def")),
-            "dr" => err(Error::item('r').at(1).expected_range("def")),
-            "deg" => err(Error::item('g').at(2).expected_range("def")),
+            "de" => err(Error::eoi().at(2).spanning((0, 2)).expected_range("def")),
+            "dr" => err(Error::item('r').at(1).spanning((0, 2)).expected_range("def")),
+            "deg" => err(Error::item('g').at(2).spanning((0, 3)).expected_range("def")),
+            "xyz" => err(Error::item('x').at(0).spanning((0, 1)).expected_range("def")),
+        });
+
+        let mut parser = range("\u{e9}t\u{e9}");
+        test_parser!(IndexedStream<&str> => &str | parser, {
+            "\u{e9}x\u{e9}" => err(
+                Error::item('x').at(2).spanning((0, 3)).expected_range("\u{e9}t\u{e9}")
+            ),
+            "\u{e9}t" => err(Error::eoi().at(3).spanning((0, 3)).expected_range("\u{e9}t\u{e9}")),
         });
     }
-}
 
-// TODO
-// pub struct SatisfyRange
+    #[test]
+    fn test_take_while() {
+        let digit = |c: &char| c.is_ascii_digit();
+        test_parser!(IndexedStream<&str> => &str | take_while(digit), {
+            "123abc" => ok("123", ("abc", 3)),
+            "abc" => ok("", ("abc", 0)),
+            "123" => ok("123", ("", 3)),
+        });
+        test_parser!(IndexedStream<&str> => &str | take_while1(digit), {
+            "1a" => ok("1", ("a", 1)),
+            "a" => err(Error::item('a').at(0).expected("at least 1 matching token")),
+            "" => err(Error::eoi().at(0).expected("at least 1 matching token")),
+        });
+        test_parser!(IndexedStream<&str> => &str | take_while_m_n(2, 3, digit), {
+            "12345" => ok("123", ("45", 3)),
+            "12a" => ok("12", ("a", 2)),
+            "1a" => err(Error::item('a').at(1).expected("2 to 3 matching tokens")),
+            "1" => err(Error::eoi().at(1).expected("2 to 3 matching tokens")),
+        });
+        test_parser!(IndexedStream<&str> => &str | take_while_m_n(2, 2, digit), {
+            "123" => ok("12", ("3", 2)),
+            "1a" => err(Error::item('a').at(1).expected("2 matching tokens")),
+        });
+        test_parser!(IndexedStream<&str> => &str | take_till(|&c: &char| c == ';'), {
+            "ab;c" => ok("ab", (";c", 2)),
+            ";" => ok("", (";", 0)),
+        });
+
+        // Positions are updated over the whole range, in the stream's units.
+        let mut parser = take_while(|&c: &char| c != '!');
+        test_parser!(State<&str, SourcePosition> => &str | parser, {
+            "\u{e9}\nb!" => ok("\u{e9}\nb", ("!", (4, 2, 2))),
+        });
+        let mut parser = take_till(|&b: &u8| b == b'!');
+        test_parser!(State<&[u8], SourcePosition> => &[u8] | parser, {
+            "\u{e9}\nb!".as_bytes() => ok("\u{e9}\nb".as_bytes(), (&b"!"[..], (4, 2, 2))),
+        });
+    }
+
+    #[test]
+    fn test_take_while_partial() {
+        let digit = |c: &char| c.is_ascii_digit();
+        test_parser!(Partial<&str> => &str | take_while(digit), {
+            "12a" => ok("12", "a"),
+            "12" => err(Error::incomplete()),
+        });
+        test_parser!(Partial<&str> => &str | take_while_m_n(1, 2, digit), {
+            "12" => ok("12", ""),
+            "1" => err(Error::incomplete().expected("1 to 2 matching tokens")),
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "m (3) is greater than n (2)")]
+    fn test_take_while_m_n_bounds() {
+        take_while_m_n::<&str, _>(3, 2, |c: &char| c.is_ascii_digit());
+    }
+}
@@ -0,0 +1,284 @@
+//! Parsers that match regular expressions against the input.
+//!
+//! Patterns are matched directly against the remaining input of streams of text or bytes, which
+//! implement [`RegexStream`], and the match is consumed with [`TokenStream::range`], so the output
+//! is borrowed from the stream.
+
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use regex::bytes;
+
+use error::{Expected, ParseResult};
+use parser::Parser;
+use stream::{Partial, Position, SharedBytes, SharedStr, State, Stream, TokenStream};
+
+/// RegexStream is implemented by streams of UTF-8 text or bytes, whose remaining input can be
+/// matched against a regular expression.
+pub trait RegexStream: Stream {
+    /// Returns the remaining input as bytes.
+    fn remaining(&self) -> &[u8];
+}
+
+impl RegexStream for &str {
+    fn remaining(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl RegexStream for &[u8] {
+    fn remaining(&self) -> &[u8] {
+        self
+    }
+}
+
+impl RegexStream for SharedStr {
+    fn remaining(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl RegexStream for SharedBytes {
+    fn remaining(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl<S: RegexStream, X: Position<S>, U: Clone + Debug> RegexStream for State<S, X, U> {
+    fn remaining(&self) -> &[u8] {
+        self.stream.remaining()
+    }
+}
+
+impl<S: RegexStream> RegexStream for Partial<S> {
+    fn remaining(&self) -> &[u8] {
+        self.stream.remaining()
+    }
+}
+
+/// A pattern compiled to match at the start of the input.
+struct Pattern {
+    source: String,
+    regex: bytes::Regex,
+}
+
+impl Pattern {
+    /// Compiles `pattern` anchored at the start of the input.
+    fn new(pattern: &str) -> Self {
+        let regex = bytes::Regex::new(&format!(r"\A(?:{})", pattern))
+            .unwrap_or_else(|e| panic!("invalid regex {:?}: {}", pattern, e));
+        Pattern {
+            source: pattern.to_string(),
+            regex,
+        }
+    }
+
+    fn expected<S: TokenStream>(&self) -> Expected<S> {
+        format!("a match for /{}/", self.source).into()
+    }
+
+    /// Searches for the pattern at the start of `stream` with `search`, which returns the end of
+    /// the match along with its output, failing if there is no match.
+    ///
+    /// On a partial stream, the result is an incomplete error if the search reached the end of
+    /// the input, since more input could change it: if the match ends there, or if there is no
+    /// match and no input left.
+    fn search<S, T, F>(&self, stream: S, search: F) -> ParseResult<S, (usize, T)>
+    where
+        S: RegexStream,
+        F: FnOnce(&bytes::Regex, &[u8]) -> Option<(usize, T)>,
+    {
+        let input = stream.remaining();
+        let len = input.len();
+        match search(&self.regex, input) {
+            Some((end, _)) if end == len && stream.is_partial() => {
+                let error = stream.end_of_input();
+                stream.err(error)
+            }
+            Some(found) => stream.ok(found),
+            None => {
+                let error = if len == 0 {
+                    stream.end_of_input()
+                } else {
+                    stream.new_error()
+                };
+                stream.err(error)
+            }
+        }
+    }
+}
+
+/// Consumes `len` units of `stream`. Fails if the match doesn't end at a character boundary of a
+/// text stream.
+fn consume<S: TokenStream>(mut stream: S, len: usize) -> ParseResult<S, S::Range> {
+    match stream.range(len) {
+        Some(range) => stream.ok(range),
+        None => {
+            let error = stream.new_error();
+            stream.err(error)
+        }
+    }
+}
+
+pub struct Regex<S> {
+    pattern: Pattern,
+    _marker: PhantomData<S>,
+}
+
+impl<S: RegexStream> Parser for Regex<S> {
+    type Stream = S;
+    type Output = S::Range;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let (found, stream) = self.pattern.search(stream, |regex, input| {
+            regex.find(input).map(|m| (m.end(), ()))
+        })?;
+        consume(stream, found.unwrap().0)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        Some(self.pattern.expected())
+    }
+}
+
+/// Parses a match of `pattern` at the current position, returning the matched range.
+///
+/// The pattern is anchored at the current position, so it only matches input starting there.
+/// Patterns follow the syntax and leftmost-first semantics of the [`regex`](https://docs.rs/regex)
+/// crate: alternatives are preferred in order rather than by length, so `regex("a|ab")` only
+/// matches the `a` of `ab`.
+///
+/// On a [partial](TokenStream::is_partial) stream, a match that reaches the end of the available
+/// input is incomplete, since more input could extend it. A match that ends before it is final,
+/// and so is a failure to match input that is available, even if more input would have let the
+/// pattern match.
+///
+/// # Panics
+///
+/// Panics if `pattern` isn't a valid regular expression.
+pub fn regex<S: RegexStream>(pattern: &str) -> Regex<S> {
+    Regex {
+        pattern: Pattern::new(pattern),
+        _marker: PhantomData,
+    }
+}
+
+pub struct RegexCaptures<S> {
+    pattern: Pattern,
+    _marker: PhantomData<S>,
+}
+
+impl<S: RegexStream> Parser for RegexCaptures<S> {
+    type Stream = S;
+    type Output = Vec<Option<S::Range>>;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let (found, stream) = self.pattern.search(stream, |regex, input| {
+            regex.captures(input).map(|captures| {
+                // The first group is the whole match.
+                let end = captures.get(0).map_or(0, |m| m.end());
+                let groups = captures
+                    .iter()
+                    .map(|group| group.map(|m| (m.start(), m.end())))
+                    .collect::<Vec<_>>();
+                (end, groups)
+            })
+        })?;
+        let (end, groups) = found.unwrap();
+        let (range, stream) = consume(stream, end)?;
+        let range = range.unwrap();
+        let captures = groups
+            .into_iter()
+            .map(|group| {
+                group.and_then(|(start, end)| {
+                    let mut range = range.clone();
+                    range.range(start)?;
+                    range.range(end - start)
+                })
+            })
+            .collect();
+        stream.ok(captures)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        Some(self.pattern.expected())
+    }
+}
+
+/// Like [`regex`], but returns the capture groups of the match: the whole match first, followed
+/// by each group in the pattern, or `None` for groups that didn't participate in the match.
+///
+/// # Panics
+///
+/// Panics if `pattern` isn't a valid regular expression.
+pub fn regex_captures<S: RegexStream>(pattern: &str) -> RegexCaptures<S> {
+    RegexCaptures {
+        pattern: Pattern::new(pattern),
+        _marker: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use stream::{IndexedStream, SourcePosition};
+
+    #[test]
+    fn test_regex() {
+        let mut parser = regex(r"[a-z_]\w*");
+        test_parser!(IndexedStream<&str> => &str | parser, {
+            "foo_1 = 2" => ok("foo_1", (" = 2", 5)),
+            // The pattern is anchored at the current position.
+            "1 foo" => err(Error::item('1').at(0).expected(r"a match for /[a-z_]\w*/")),
+            "" => err(Error::eoi().at(0).expected(r"a match for /[a-z_]\w*/")),
+        });
+
+        // Alternatives are anchored as a whole.
+        let mut parser = regex(r"a|b");
+        test_parser!(&str => &str | parser, {
+            "ba" => ok("b", "a"),
+            "ca" => err(Error::item('c').expected("a match for /a|b/")),
+        });
+
+        // The first alternative that matches is taken, not the longest.
+        let mut parser = regex(r"a|ab");
+        test_parser!(&str => &str | parser, {
+            "ab" => ok("a", "b"),
+        });
+
+        let mut parser = regex(r"\d+");
+        test_parser!(State<&[u8], SourcePosition> => &[u8] | parser, {
+            &b"12\n"[..] => ok(&b"12"[..], (&b"\n"[..], (2, 1, 3))),
+        });
+        test_parser!(Partial<&str> => &str | regex(r"\d+"), {
+            "12;" => ok("12", ";"),
+            "12" => err(Error::incomplete().expected(r"a match for /\d+/")),
+        });
+
+        // Only a search that reaches the end of the input is incomplete.
+        test_parser!(Partial<&str> => &str | regex(r"\d\d"), {
+            "12;" => ok("12", ";"),
+            "" => err(Error::incomplete().expected(r"a match for /\d\d/")),
+            "1;" => err(Error::item('1').expected(r"a match for /\d\d/")),
+        });
+        test_parser!(Partial<&str> => &str | regex(r"abc|a"), {
+            "ab" => ok("a", "b"),
+        });
+        test_parser!(Partial<&str> => &str | regex(r"\w+\b"), {
+            "caf\u{e9} au lait" => ok("caf\u{e9}", " au lait"),
+            "caf\u{e9}" => err(Error::incomplete().expected(r"a match for /\w+\b/")),
+            " caf\u{e9}" => err(Error::item(' ').expected(r"a match for /\w+\b/")),
+        });
+    }
+
+    #[test]
+    fn test_regex_captures() {
+        let mut parser = regex_captures(r"(\w+)=(\d+)?");
+        test_parser!(IndexedStream<&str> => Vec<Option<&str>> | parser, {
+            "x=12;" => ok(vec![Some("x=12"), Some("x"), Some("12")], (";", 4)),
+            "\u{e9}=;" => ok(vec![Some("\u{e9}="), Some("\u{e9}"), None], (";", 3)),
+            "=1" => err(Error::item('=').at(0).expected(r"a match for /(\w+)=(\d+)?/")),
+        });
+    }
+}
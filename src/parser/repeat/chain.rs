@@ -0,0 +1,176 @@
+use std::mem;
+
+use crate::{Expected, ParseResult, Parser, TokenStream};
+
+pub struct Chainl1<P, Op> {
+    p: P,
+    op: Op,
+}
+
+impl<P, Op> Parser for Chainl1<P, Op>
+where
+    P: Parser,
+    Op: Parser<Stream = P::Stream>,
+    Op::Output: FnOnce(P::Output, P::Output) -> P::Output,
+{
+    type Stream = P::Stream;
+    type Output = P::Output;
+
+    fn parse_partial(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let (mut output, mut stream) = match self.p.parse_partial(stream) {
+            Ok((Some(result), stream)) => (result, stream),
+            Ok((None, stream)) => return stream.noop(),
+            Err(err) => return Err(err),
+        };
+
+        loop {
+            stream = match self.op.by_ref().and(self.p.by_ref()).parse(stream) {
+                Ok((Some((f, result)), stream)) => {
+                    output = f(output, result);
+                    stream
+                }
+                Ok((None, stream)) => return stream.noop(),
+                // With more input, another operand might follow.
+                Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
+                Err((_, stream)) => return stream.ok(output),
+            };
+        }
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.p.expected_error()
+    }
+}
+
+/// Parses one or more `p` separated by `op`, folding the outputs of `p` from the left with the
+/// functions output by `op`, so that `a - b - c` is folded as `(a - b) - c`.
+///
+/// Like [`sep_by1`](super::sep_by1), an `op` that isn't followed by `p` isn't consumed.
+pub fn chainl1<P, Op>(p: P, op: Op) -> Chainl1<P, Op>
+where
+    P: Parser,
+    Op: Parser<Stream = P::Stream>,
+    Op::Output: FnOnce(P::Output, P::Output) -> P::Output,
+{
+    Chainl1 { p, op }
+}
+
+pub struct Chainr1<P, Op> {
+    p: P,
+    op: Op,
+}
+
+impl<P, Op> Parser for Chainr1<P, Op>
+where
+    P: Parser,
+    Op: Parser<Stream = P::Stream>,
+    Op::Output: FnOnce(P::Output, P::Output) -> P::Output,
+{
+    type Stream = P::Stream;
+    type Output = P::Output;
+
+    fn parse_partial(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let (mut output, mut stream) = match self.p.parse_partial(stream) {
+            Ok((Some(result), stream)) => (result, stream),
+            Ok((None, stream)) => return stream.noop(),
+            Err(err) => return Err(err),
+        };
+
+        // Each operand is held along with the operator that follows it until the end of the chain
+        // is found, so that they can be folded from the right.
+        let mut pending = Vec::new();
+        stream = loop {
+            stream = match self.op.by_ref().and(self.p.by_ref()).parse(stream) {
+                Ok((Some((f, result)), stream)) => {
+                    pending.push((mem::replace(&mut output, result), f));
+                    stream
+                }
+                Ok((None, stream)) => return stream.noop(),
+                // With more input, another operand might follow.
+                Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
+                Err((_, stream)) => break stream,
+            };
+        };
+
+        while let Some((lhs, f)) = pending.pop() {
+            output = f(lhs, output);
+        }
+        stream.ok(output)
+    }
+
+    fn expected_error(&self) -> Option<Expected<Self::Stream>> {
+        self.p.expected_error()
+    }
+}
+
+/// Parses one or more `p` separated by `op`, folding the outputs of `p` from the right with the
+/// functions output by `op`, so that `a ^ b ^ c` is folded as `a ^ (b ^ c)`.
+///
+/// Like [`sep_by1`](super::sep_by1), an `op` that isn't followed by `p` isn't consumed.
+pub fn chainr1<P, Op>(p: P, op: Op) -> Chainr1<P, Op>
+where
+    P: Parser,
+    Op: Parser<Stream = P::Stream>,
+    Op::Output: FnOnce(P::Output, P::Output) -> P::Output,
+{
+    Chainr1 { p, op }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::item::{ascii, item};
+    use crate::stream::IndexedStream;
+    use crate::Error;
+
+    fn number() -> impl Parser<Stream = IndexedStream<&'static str>, Output = i64> {
+        ascii::digit().map(|c: char| c.to_digit(10).unwrap() as i64)
+    }
+
+    #[test]
+    fn test_chainl1() {
+        let mut parser = chainl1(number(), item(b'-').map(|_| |l: i64, r: i64| l - r));
+        test_parser!(IndexedStream<&'static str> => i64 | parser, {
+            "8" => ok(8, ("", 1)),
+            "8-2-1" => ok(5, ("", 5)),
+            "8-2-" => ok(6, ("-", 3)),
+            "8-2--1" => ok(6, ("--1", 3)),
+            "" => err(Error::eoi().expected("an ascii digit").at(0)),
+            "-1" => err(Error::item('-').expected("an ascii digit").at(0)),
+        });
+
+        // The chain is expected to start with an operand.
+        let mut parser = item(b'(').map(|_| 0).or(chainl1(
+            number(),
+            item(b'-').map(|_| |l: i64, r: i64| l - r),
+        ));
+        test_parser!(IndexedStream<&'static str> => i64 | parser, {
+            "x" => err(
+                Error::item('x')
+                    .expected_one_of(vec![Expected::item('('), "an ascii digit".into()])
+                    .at(0)
+            ),
+        });
+    }
+
+    #[test]
+    fn test_chainr1() {
+        let mut parser = chainr1(number(), item(b'^').map(|_| |l: i64, r| l.pow(r as u32)));
+        test_parser!(IndexedStream<&'static str> => i64 | parser, {
+            "2" => ok(2, ("", 1)),
+            "2^3^2" => ok(512, ("", 5)),
+            "2^3^" => ok(8, ("^", 3)),
+            "2^3^^2" => ok(8, ("^^2", 3)),
+            "" => err(Error::eoi().expected("an ascii digit").at(0)),
+            "^1" => err(Error::item('^').expected("an ascii digit").at(0)),
+        });
+
+        // Long chains don't overflow the stack.
+        let input = vec!["1"; 200_000].join("^");
+        let mut parser = chainr1(
+            ascii::digit().map(|c: char| c.to_digit(10).unwrap() as i64),
+            item(b'^').map(|_| |l: i64, r| l.pow(r as u32)),
+        );
+        assert_eq!(parser.parse(&input[..]), Ok((Some(1), "")));
+    }
+}
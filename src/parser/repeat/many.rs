@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 
-use crate::error::Expected;
-use {ParseResult, Parser, Stream};
+use crate::error::{Error, Expected};
+use {ParseResult, Parser, TokenStream};
 
 pub struct Many<O, P> {
     p: P,
@@ -28,12 +28,15 @@ where
                 }
             }
 
+            let start = stream.backup();
             stream = match self.p.try_parse_lazy(stream) {
                 Ok((Some(result), stream)) => {
                     output.extend(std::iter::once(result));
                     stream
                 }
                 Ok((None, stream)) => stream,
+                // With more input, `p` might succeed again.
+                Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
                 Err((error, stream)) => {
                     if i < self.min {
                         return stream.err(error);
@@ -43,12 +46,20 @@ where
             };
 
             i += 1;
+            // `p` would go on succeeding without consuming any input forever.
+            if i >= self.min && start.distance(&stream) == 0 {
+                return stream.ok(output);
+            }
         }
     }
 
     fn expected_error(&self) -> Option<Expected<Self::Stream>> {
         self.p.expected_error()
     }
+
+    fn add_expected_error(&self, error: &mut Error<Self::Stream>) {
+        self.p.add_expected_error(error);
+    }
 }
 
 pub fn many<O, P>(p: P) -> Many<O, P>
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::{ParseResult, Parser, Stream};
+use crate::{ParseResult, Parser, TokenStream};
 
 pub struct SepBy<O, P, Sep> {
     p: P,
@@ -30,6 +30,8 @@ where
                 stream
             }
             Ok((None, stream)) => stream,
+            // With more input, another element might follow.
+            Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
             Err((error, stream)) => {
                 if i < self.min {
                     return stream.err(error);
@@ -40,12 +42,14 @@ where
         i += 1;
 
         loop {
+            let start = stream.backup();
             stream = match self.sep.by_ref().with(self.p.by_ref()).parse(stream) {
                 Ok((Some(result), stream)) => {
                     output.extend(std::iter::once(result));
                     stream
                 }
                 Ok((None, stream)) => stream,
+                Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
                 Err((error, stream)) => {
                     if i < self.min {
                         return stream.err(error);
@@ -54,6 +58,10 @@ where
                 }
             };
             i += 1;
+            // `sep` and `p` would go on succeeding without consuming any input forever.
+            if start.distance(&stream) == 0 {
+                return stream.ok(output);
+            }
         }
     }
 }
@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 use crate::error::Expected;
 use crate::parser::combinator::lookahead;
-use {Error, ParseResult, Parser, Stream};
+use {ParseResult, Parser, TokenStream};
 
 pub struct TakeUntil<O, P, U> {
     p: P,
@@ -25,6 +25,8 @@ where
             stream = match lookahead(self.until.by_ref()).parse_lazy(stream) {
                 Ok((Some(_), stream)) => return stream.ok(output),
                 Ok((None, stream)) => stream,
+                // With more input, `until` might succeed here.
+                Err((error, stream)) if error.is_incomplete() => return Err((error, stream)),
                 Err((_, stream)) => stream,
             };
 
@@ -33,7 +35,10 @@ where
                     output.extend(std::iter::once(value));
                     stream
                 }
-                (None, stream) => return stream.err(Error::eoi()),
+                (None, stream) => {
+                    let error = stream.end_of_input();
+                    return stream.err(error);
+                }
             };
         }
     }
@@ -1,11 +1,11 @@
-use crate::{Expected, ParseResult, Parser, Stream};
+use crate::{Expected, ParseResult, Parser, TokenStream};
 
 pub struct And<L, R> {
     p1: L,
     p2: R,
 }
 
-impl<S: Stream, L, R> Parser for And<L, R>
+impl<S: TokenStream, L, R> Parser for And<L, R>
 where
     L: Parser<Stream = S>,
     R: Parser<Stream = S>,
@@ -33,7 +33,7 @@ where
 /// Equivalent to [`p1.and(p2)`].
 ///
 /// [`p1.and(p2)`]: Parser::and
-pub fn and<S: Stream, O, L, R>(p1: L, p2: R) -> And<L, R>
+pub fn and<S: TokenStream, O, L, R>(p1: L, p2: R) -> And<L, R>
 where
     L: Parser<Stream = S>,
     R: Parser<Stream = S, Output = O>,
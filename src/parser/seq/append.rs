@@ -1,4 +1,4 @@
-use crate::{ParseResult, Parser, Stream};
+use crate::{ParseResult, Parser, TokenStream};
 
 pub struct Append<L, R> {
     p1: L,
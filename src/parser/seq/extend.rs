@@ -2,7 +2,7 @@ use std::iter;
 
 use error::ParseResult;
 use parser::Parser;
-use stream::Stream;
+use stream::TokenStream;
 
 pub struct Extend<L, R> {
     p1: L,
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use {ParseResult, Parser, Stream};
+use {ParseResult, Parser, TokenStream};
 
 pub struct Then<I, L, R> {
     p1: L,
@@ -1,6 +1,6 @@
-use {ParseResult, Parser, Stream};
+use {ParseResult, Parser, TokenStream};
 
-impl<S: Stream, P0, P1> Parser for (P0, P1)
+impl<S: TokenStream, P0, P1> Parser for (P0, P1)
 where
     P0: Parser<Stream = S>,
     P1: Parser<Stream = S>,
@@ -27,7 +27,7 @@ where
     }
 }
 
-impl<S: Stream, P0, P1, P2> Parser for (P0, P1, P2)
+impl<S: TokenStream, P0, P1, P2> Parser for (P0, P1, P2)
 where
     P0: Parser<Stream = S>,
     P1: Parser<Stream = S>,
@@ -0,0 +1,161 @@
+//! Parsers that read and update the user state of a [`StateStream`].
+//!
+//! The state can also be accessed directly from the stream in [`and_then`](Parser::and_then)
+//! closures and [`parser`](super::parser) functions.
+
+use std::marker::PhantomData;
+
+use error::ParseResult;
+use parser::Parser;
+use stream::StateStream;
+
+pub struct GetState<S>(PhantomData<S>);
+
+impl<S> Parser for GetState<S>
+where
+    S: StateStream,
+    S::State: Clone,
+{
+    type Stream = S;
+    type Output = S::State;
+
+    fn parse_lazy(&mut self, stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let state = stream.state().clone();
+        stream.ok(state)
+    }
+}
+
+/// Outputs a copy of the user state, without consuming any input.
+pub fn get_state<S>() -> GetState<S>
+where
+    S: StateStream,
+    S::State: Clone,
+{
+    GetState(PhantomData)
+}
+
+pub struct UpdateState<S, F> {
+    f: F,
+    _marker: PhantomData<S>,
+}
+
+impl<S, F, O> Parser for UpdateState<S, F>
+where
+    S: StateStream,
+    F: FnMut(&mut S::State) -> O,
+{
+    type Stream = S;
+    type Output = O;
+
+    fn parse_lazy(&mut self, mut stream: Self::Stream) -> ParseResult<Self::Stream, Self::Output> {
+        let output = (self.f)(stream.state_mut());
+        stream.ok(output)
+    }
+}
+
+/// Calls `f` with the user state, and outputs its result, without consuming any input.
+pub fn update_state<S, F, O>(f: F) -> UpdateState<S, F>
+where
+    S: StateStream,
+    F: FnMut(&mut S::State) -> O,
+{
+    UpdateState {
+        f,
+        _marker: PhantomData,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::*;
+    use error::Error;
+    use parser::choice::optional;
+    use parser::combinator::attempt;
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::repeat::{many, many1, sep_by1};
+    use stream::{IndexPosition, State, TokenStream};
+
+    type Scope = State<&'static str, IndexPosition, HashSet<String>>;
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_state() {
+        let name = || many1::<String, _>(ascii::letter());
+        let declaration = range("let ")
+            .with(name())
+            .and_then(|name, mut stream: Scope| {
+                stream.state_mut().insert(name);
+                stream.ok(())
+            });
+        let reference = name().and_then(|name, stream: Scope| {
+            if stream.state().contains(&name) {
+                stream.ok(name)
+            } else {
+                stream.err(Error::from(format!("undeclared name `{}`", name)))
+            }
+        });
+        let mut parser =
+            many::<Vec<_>, _>(declaration.skip(item(b';')))
+                .with(sep_by1::<Vec<_>, _, _>(reference, item(b',')));
+        test_parser!(Scope => Vec<String> | parser, {
+            "let x;let y;x,y" => ok(
+                vec!["x".to_string(), "y".to_string()],
+                Scope::with_state("", 15, names(&["x", "y"]))
+            ),
+            "let x;y" => err(
+                Error::from("undeclared name `y`")
+                    .at(7)
+                    .spanning((6, 7))
+                    .expected("an ascii letter")
+            ),
+        });
+
+        let mut parser = get_state::<Scope>();
+        let (result, _) = parser
+            .parse(Scope::with_state("", 0, names(&["x"])))
+            .unwrap();
+        assert_eq!(result, Some(names(&["x"])));
+    }
+
+    #[test]
+    fn test_update_state() {
+        let nested = || update_state(|depth: &mut u32| *depth += 1).with(item(b'('));
+        // The depth is restored when `attempt` backtracks.
+        let mut parser = optional(attempt(nested().with(item(b'!'))))
+            .with(many::<Vec<_>, _>(nested()))
+            .with(get_state());
+        test_parser!(State<&str, IndexPosition, u32> => u32 | parser, {
+            "(((" => ok(3, State::with_state("", 3, 3)),
+            "(!((" => ok(3, State::with_state("", 4, 3)),
+            "(!" => ok(1, State::with_state("", 2, 1)),
+        });
+    }
+
+    #[test]
+    fn test_restore_state() {
+        let mut stream = Scope::with_state("let x", 0, names(&["x"]));
+        let backup = stream.backup();
+        stream.state_mut().insert("y".to_string());
+        stream.pop();
+        stream.restore(backup);
+        assert_eq!(stream, Scope::with_state("let x", 0, names(&["x"])));
+
+        // A branch that fails without consuming input leaves the state as it found it.
+        let declare = |name: &'static str| {
+            update_state(move |names: &mut HashSet<String>| names.insert(name.to_string()))
+        };
+        let mut parser = declare("y")
+            .with(item(b'!'))
+            .or(declare("z").with(item(b'?')))
+            .with(get_state());
+        test_parser!(Scope => HashSet<String> | parser, {
+            "?" => ok(names(&["z"]), Scope::with_state("", 1, names(&["z"]))),
+        });
+    }
+}
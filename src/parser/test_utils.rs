@@ -1,10 +1,10 @@
 #[cfg(test)]
-use {ParseResult, Stream};
+use {ParseResult, TokenStream};
 
 #[cfg(test)]
 pub fn ok_result<S, O, I>(output: O, stream: I) -> ParseResult<S, O>
 where
-    S: Stream,
+    S: TokenStream,
     I: Into<S>,
 {
     Ok((Some(output), stream.into()))
@@ -13,7 +13,7 @@ where
 #[cfg(test)]
 pub fn none_result<S, O, I>(stream: I) -> ParseResult<S, O>
 where
-    S: Stream,
+    S: TokenStream,
     I: Into<S>,
 {
     Ok((None, stream.into()))
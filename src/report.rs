@@ -0,0 +1,419 @@
+//! Human-readable rendering of parse errors against the input they were produced from.
+
+use std::fmt;
+
+use error::{Error, Expected, Info};
+use stream::{Position, Token, TokenStream};
+use traits::AsBytes;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// A diagnostic report for an [`Error`], rendered with [`Display`](fmt::Display).
+///
+/// The report shows the line of `source` that contains the error, an underline beneath the
+/// offending input, the expected input as a list, the error's context, innermost first, and its
+/// hints:
+///
+/// ```text
+/// error: unexpected item 'x'
+///  --> line 2, col 5
+///   |
+/// 2 | bar x
+///   |     ^
+///   = expected one of:
+///       - 'y'
+///       - an ascii digit
+///   = while parsing a value
+///   = help: did you mean `y`?
+/// ```
+///
+/// The header names the file the error is in if one is given with [`name`](Report::name), as
+/// [`SourceMap::report`](::source_map::SourceMap::report) does.
+///
+/// If the error's position can't be located in `source` (for example, streams using
+/// [`NullPosition`](::stream::NullPosition)), the snippet is omitted.
+pub struct Report<'a, S: TokenStream> {
+    source: &'a [u8],
+    name: Option<&'a str>,
+    error: &'a Error<S>,
+    color: bool,
+}
+
+impl<'a, S: TokenStream> Report<'a, S> {
+    /// Create a plain-text `Report` for `error`, which was produced by parsing `source`.
+    pub fn new<T: AsBytes + ?Sized>(source: &'a T, error: &'a Error<S>) -> Self {
+        Report {
+            source: source.as_bytes(),
+            name: None,
+            error,
+            color: false,
+        }
+    }
+
+    /// Sets the name of the file `source` was read from, shown in the report's header.
+    /// Chainable.
+    pub fn name(mut self, name: &'a str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Sets whether the report is rendered with ANSI colors. Chainable.
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, style: &'static str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Returns the number of characters the cause of the error spans in the input.
+    fn cause_width(&self) -> usize {
+        match &self.error.cause {
+            Info::Range(range) => range.tokens().filter(|t| t.starts_char()).count().max(1),
+            _ => 1,
+        }
+    }
+
+    fn fmt_snippet(&self, f: &mut fmt::Formatter, offset: usize) -> fmt::Result {
+        let line_start = self.source[..offset]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let line_end = self.source[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(self.source.len(), |i| offset + i);
+        let line_number = self.source[..line_start]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+            + 1;
+        let text = String::from_utf8_lossy(&self.source[line_start..line_end]);
+        let text = text.trim_end_matches('\r');
+        let column = self.count_chars(line_start, offset);
+
+        // Underline the part of the error's span that's on this line, or the cause of the
+        // error if it has no span.
+        let underline = self
+            .error
+            .span
+            .as_ref()
+            .and_then(|span| {
+                let start = span.start.offset_in(self.source)?.max(line_start);
+                let end = span.end.offset_in(self.source)?.min(line_end);
+                (start < end).then_some((start, end))
+            })
+            .map(|(start, end)| {
+                (
+                    self.count_chars(line_start, start),
+                    self.count_chars(start, end),
+                )
+            });
+        let (indent, width) = underline.unwrap_or_else(|| {
+            let width = self
+                .cause_width()
+                .min(text.chars().count().saturating_sub(column).max(1));
+            (column, width)
+        });
+
+        let number = line_number.to_string();
+        let gutter = " ".repeat(number.len());
+        let bar = self.paint(BLUE, "|");
+        write!(f, "{}{} ", gutter, self.paint(BLUE, "-->"))?;
+        if let Some(name) = self.name {
+            write!(f, "{}, ", name)?;
+        }
+        writeln!(f, "line {}, col {}", line_number, column + 1)?;
+        writeln!(f, "{} {}", gutter, bar)?;
+        writeln!(f, "{} {} {}", self.paint(BLUE, &number), bar, text)?;
+        write!(
+            f,
+            "{} {} {}{}",
+            gutter,
+            bar,
+            " ".repeat(indent),
+            self.paint(RED, &"^".repeat(width))
+        )
+    }
+
+    /// Returns the number of characters in `source[start..end]`.
+    fn count_chars(&self, start: usize, end: usize) -> usize {
+        String::from_utf8_lossy(&self.source[start..end])
+            .chars()
+            .count()
+    }
+
+    fn fmt_expected(
+        &self,
+        f: &mut fmt::Formatter,
+        expected: &Expected<S>,
+        indent: usize,
+    ) -> fmt::Result {
+        let pad = " ".repeat(indent);
+        match expected {
+            Expected::Info(_) => write!(f, "\n{}- {}", pad, expected),
+            Expected::OneOf(errors) | Expected::Seq(errors) => {
+                let header = match expected {
+                    Expected::OneOf(_) => "one of:",
+                    _ => "in sequence:",
+                };
+                write!(f, "\n{}- {}", pad, header)?;
+                for e in errors {
+                    self.fmt_expected(f, e, indent + 4)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a, S: TokenStream> fmt::Display for Report<'a, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{} unexpected {}",
+            self.paint(RED, "error"),
+            self.paint(BOLD, ":"),
+            self.error.cause
+        )?;
+
+        let offset = self.error.position.offset_in(self.source);
+        let gutter = match offset {
+            Some(offset) => {
+                writeln!(f)?;
+                self.fmt_snippet(f, offset)?;
+                let lines = self.source[..offset]
+                    .iter()
+                    .filter(|&&b| b == b'\n')
+                    .count();
+                " ".repeat((lines + 1).to_string().len())
+            }
+            None => String::new(),
+        };
+
+        match &self.error.expected {
+            Some(expected @ Expected::Info(_)) => {
+                write!(
+                    f,
+                    "\n{} {} expected {}",
+                    gutter,
+                    self.paint(BLUE, "="),
+                    expected
+                )?;
+            }
+            Some(Expected::OneOf(errors)) | Some(Expected::Seq(errors)) => {
+                let header = match self.error.expected {
+                    Some(Expected::OneOf(_)) => "one of",
+                    _ => "in sequence",
+                };
+                write!(
+                    f,
+                    "\n{} {} expected {}:",
+                    gutter,
+                    self.paint(BLUE, "="),
+                    header
+                )?;
+                for e in errors {
+                    self.fmt_expected(f, e, gutter.len() + 5)?;
+                }
+            }
+            None => {}
+        }
+
+        for label in &self.error.context {
+            write!(
+                f,
+                "\n{} {} while parsing {}",
+                gutter,
+                self.paint(BLUE, "="),
+                label
+            )?;
+        }
+        for hint in &self.error.hints {
+            write!(f, "\n{} {} help: {}", gutter, self.paint(BLUE, "="), hint)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: TokenStream> Error<S> {
+    /// Create a [`Report`] that renders this error against `source`, the input it was produced
+    /// from.
+    pub fn report<'a, T: AsBytes + ?Sized>(&'a self, source: &'a T) -> Report<'a, S> {
+        Report::new(source, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::repeat::many1;
+    use parser::Parser;
+    use stream::{IndexedStream, LinePosition, SourceCode, State, Stream};
+
+    fn parser<S: Stream>() -> impl Parser<Stream = S, Output = S::Item> {
+        range("foo\nbar ").with(choice![item(b'y'), ascii::digit()])
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "foo\nbar x\n";
+        let (error, _) = parser().parse(SourceCode::from(input)).unwrap_err();
+        assert_eq!(
+            error.report(input).to_string(),
+            "\
+error: unexpected item 'x'
+ --> line 2, col 5
+  |
+2 | bar x
+  |     ^
+  = expected one of:
+      - 'y'
+      - an ascii digit"
+        );
+
+        let expected = error.report(input).to_string();
+
+        let (error, _) = parser()
+            .parse(IndexedStream::from(input.as_bytes()))
+            .unwrap_err();
+        assert_eq!(error.report(input.as_bytes()).to_string(), expected);
+
+        let (error, _) = parser()
+            .parse(State::<&[u8], LinePosition>::from(input.as_bytes()))
+            .unwrap_err();
+        assert_eq!(Report::new(input, &error).to_string(), expected);
+    }
+
+    #[test]
+    fn test_report_range() {
+        let input = "let x = 1;";
+        let mut parser = range("let x = 1").with(range(" ;"));
+        let (error, _) = parser.parse(IndexedStream::from(input)).unwrap_err();
+        assert_eq!(
+            error.report(input).to_string(),
+            "\
+error: unexpected item ';'
+ --> line 1, col 10
+  |
+1 | let x = 1;
+  |          ^
+  = expected \" ;\""
+        );
+    }
+
+    #[test]
+    fn test_report_span() {
+        let input = "x = 1\ny = 1024\n";
+        let mut parser =
+            range("x = 1\ny = ").with(many1::<String, _>(ascii::digit()).from_str::<u8>());
+        let (error, _) = parser.parse(SourceCode::from(input)).unwrap_err();
+        assert_eq!(
+            error.report(input).to_string(),
+            "\
+error: unexpected number too large to fit in target type
+ --> line 2, col 9
+  |
+2 | y = 1024
+  |     ^^^^"
+        );
+    }
+
+    #[test]
+    fn test_report_non_ascii() {
+        fn parser<S: Stream>() -> impl Parser<Stream = S, Output = S::Range> {
+            range("\u{e9}t\u{e9} = ").with(range("\u{e9}t"))
+        }
+        let input = "\u{e9}t\u{e9} = \u{e9}x";
+        let expected = "\
+error: unexpected item 'x'
+ --> line 1, col 8
+  |
+1 | \u{e9}t\u{e9} = \u{e9}x
+  |       ^^
+  = expected \"\u{e9}t\"";
+        let (error, _) = parser().parse(SourceCode::from(input)).unwrap_err();
+        assert_eq!(error.report(input).to_string(), expected);
+        let (error, _) = parser().parse(IndexedStream::from(input)).unwrap_err();
+        assert_eq!(error.report(input).to_string(), expected);
+        let (error, _) = parser()
+            .parse(IndexedStream::from(input.as_bytes()))
+            .unwrap_err();
+        assert_eq!(error.report(input).to_string(), expected);
+    }
+
+    #[test]
+    fn test_report_eoi() {
+        let input = "ab";
+        let mut parser = range("abc");
+        let (error, _) = parser.parse(SourceCode::from(input)).unwrap_err();
+        assert_eq!(
+            error.report(input).to_string(),
+            "\
+error: unexpected end of input
+ --> line 1, col 3
+  |
+1 | ab
+  | ^^
+  = expected \"abc\""
+        );
+    }
+
+    #[test]
+    fn test_report_context() {
+        let input = "foo\nbar x\n";
+        let mut parser = parser().context("a value").context("a record");
+        let (error, _) = parser.parse(SourceCode::from(input)).unwrap_err();
+        assert_eq!(
+            error.report(input).to_string(),
+            "\
+error: unexpected item 'x'
+ --> line 2, col 5
+  |
+2 | bar x
+  |     ^
+  = expected one of:
+      - 'y'
+      - an ascii digit
+  = while parsing a value
+  = while parsing a record"
+        );
+
+        let error = error.hint("did you mean `y`?");
+        assert!(error
+            .report(input)
+            .to_string()
+            .ends_with("  = while parsing a record\n  = help: did you mean `y`?"));
+    }
+
+    #[test]
+    fn test_report_without_position() {
+        let input = "?";
+        let (error, _) = ascii::digit().parse(input).unwrap_err();
+        assert_eq!(
+            error.report(input).to_string(),
+            "error: unexpected item '?'\n = expected an ascii digit"
+        );
+    }
+
+    #[test]
+    fn test_report_color() {
+        let input = "?";
+        let (error, _) = ascii::digit()
+            .parse(IndexedStream::from(input))
+            .unwrap_err();
+        let report = error.report(input).color(true).to_string();
+        assert!(report.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(report.contains("\x1b[1;31m^\x1b[0m"));
+    }
+}
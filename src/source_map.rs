@@ -0,0 +1,141 @@
+//! A registry of source files, for parsing input spread across several files.
+//!
+//! Each file added to a [`SourceMap`] is given a [`FileId`], and is parsed from a stream
+//! positioned by [`FilePosition`]s, so that errors can be resolved back to the file they came
+//! from and rendered against its source text.
+
+use error::Error;
+use report::Report;
+use stream::{FileId, FilePosition, Position, State, TokenStream};
+
+/// A source file registered in a [`SourceMap`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceFile {
+    name: String,
+    source: String,
+}
+
+impl SourceFile {
+    /// Returns the name of the file, such as its path.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the source text of the file.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+/// SourceMap holds the name and source text of each file being parsed.
+///
+/// ```
+/// # extern crate rparse;
+/// # use rparse::source_map::SourceMap;
+/// # use rparse::parser::range::range;
+/// # use rparse::Parser;
+/// # fn main() {
+/// let mut files = SourceMap::new();
+/// let main = files.add("main.txt", "include lib.txt\n");
+/// let lib = files.add("lib.txt", "foo\nbaz\n");
+///
+/// assert!(range("include ").parse(files.stream(main).unwrap()).is_ok());
+///
+/// let (error, _) = range("foo\nbar").parse(files.stream(lib).unwrap()).unwrap_err();
+/// let report = files.report(&error).unwrap().to_string();
+/// assert!(report.contains(" --> lib.txt, line 2, col 3"));
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    /// Registers a file with `name` and `source` text, and returns its id.
+    pub fn add<N: Into<String>, T: Into<String>>(&mut self, name: N, source: T) -> FileId {
+        let id = FileId::from(self.files.len() as u32);
+        self.files.push(SourceFile {
+            name: name.into(),
+            source: source.into(),
+        });
+        id
+    }
+
+    /// Returns the file with `id`, or `None` if it isn't registered in this map.
+    pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get(id.index())
+    }
+
+    /// Returns the file that `position` is in, or `None` if it isn't registered in this map.
+    pub fn resolve(&self, position: &FilePosition) -> Option<&SourceFile> {
+        self.get(position.file)
+    }
+
+    /// Returns a stream over the source text of the file with `id`, starting at its first line.
+    pub fn stream(&self, id: FileId) -> Option<State<&str, FilePosition>> {
+        self.get(id)
+            .map(|file| State::new(file.source(), FilePosition::from(id)))
+    }
+
+    /// Create a [`Report`] that renders `error` against the source text of the file it was
+    /// produced from, named by the file's name. Returns `None` if the error's position doesn't
+    /// identify a file registered in this map.
+    pub fn report<'a, S: TokenStream>(&'a self, error: &'a Error<S>) -> Option<Report<'a, S>> {
+        error
+            .position
+            .file()
+            .and_then(|id| self.get(id))
+            .map(|file| Report::new(file.source(), error).name(file.name()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::repeat::many1;
+    use parser::Parser;
+
+    #[test]
+    fn test_source_map() {
+        let mut files = SourceMap::new();
+        let main = files.add("main.txt", "x = 1\n");
+        let lib = files.add("lib.txt", "y = 2\nz = ?\n");
+        assert_eq!(files.get(lib).map(SourceFile::name), Some("lib.txt"));
+        assert_eq!(files.get(FileId::from(2)), None);
+
+        let mut parser = many1::<Vec<_>, _>(
+            ascii::letter()
+                .skip(range(" = "))
+                .and(ascii::digit())
+                .skip(item(b'\n')),
+        );
+        let (result, stream) = parser.parse(files.stream(main).unwrap()).unwrap();
+        assert_eq!(result, Some(vec![('x', '1')]));
+        assert_eq!(stream.position, FilePosition::from((main, 6, 2, 1)));
+
+        let mut parser = range("y = 2\nz = ").with(ascii::digit());
+        let (error, _) = parser.parse(files.stream(lib).unwrap()).unwrap_err();
+        assert_eq!(error.position, FilePosition::from((lib, 10, 2, 5)));
+        assert_eq!(files.resolve(&error.position), files.get(lib));
+        assert_eq!(
+            files.report(&error).unwrap().to_string(),
+            "\
+error: unexpected item '?'
+ --> lib.txt, line 2, col 5
+  |
+2 | z = ?
+  |     ^
+  = expected an ascii digit"
+        );
+
+        let other = SourceMap::new();
+        assert!(other.report(&error).is_none());
+    }
+}
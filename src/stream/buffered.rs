@@ -0,0 +1,494 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::io::{self, Read};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use super::{NullPosition, RangeStream, SharedBytes, TokenStream, Tokens};
+
+/// The number of bytes requested from the reader at a time.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+struct Buffer<R> {
+    reader: R,
+    /// The buffered input, in the chunks it was read in, along with the offset of each chunk.
+    chunks: VecDeque<(usize, Arc<[u8]>)>,
+    /// The offset of the end of the buffered input.
+    end: usize,
+    /// The number of streams sharing the buffer at each offset.
+    streams: BTreeMap<usize, usize>,
+    eof: bool,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> Buffer<R> {
+    /// Reads from the reader until the input up to offset `end` is buffered, or the reader is
+    /// exhausted.
+    fn fill(&mut self, end: usize) {
+        while self.end < end && !self.eof {
+            self.discard();
+            let mut chunk = vec![0; CHUNK_SIZE];
+            match self.reader.read(&mut chunk) {
+                Ok(0) => self.eof = true,
+                Ok(n) => {
+                    chunk.truncate(n);
+                    self.chunks.push_back((self.end, Arc::from(chunk)));
+                    self.end += n;
+                }
+                Err(e) => {
+                    if e.kind() != io::ErrorKind::Interrupted {
+                        self.error = Some(e);
+                        self.eof = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops the chunks that are behind every stream sharing the buffer.
+    fn discard(&mut self) {
+        let lowest = self.streams.keys().next().cloned().unwrap_or(self.end);
+        while self
+            .chunks
+            .front()
+            .is_some_and(|(start, chunk)| start + chunk.len() <= lowest)
+        {
+            self.chunks.pop_front();
+        }
+    }
+
+    fn track(&mut self, offset: usize) {
+        *self.streams.entry(offset).or_insert(0) += 1;
+    }
+
+    fn untrack(&mut self, offset: usize) {
+        if let Some(count) = self.streams.get_mut(&offset) {
+            *count -= 1;
+            if *count == 0 {
+                self.streams.remove(&offset);
+            }
+        }
+    }
+
+    /// Returns the index of the chunk holding the input at `offset`, or `None` if it isn't
+    /// buffered.
+    fn find(&self, offset: usize) -> Option<usize> {
+        let i = self.chunks.partition_point(|&(start, _)| start <= offset);
+        let (start, ref chunk) = *self.chunks.get(i.checked_sub(1)?)?;
+        (offset < start + chunk.len()).then_some(i - 1)
+    }
+
+    fn get(&self, offset: usize) -> Option<u8> {
+        let (start, ref chunk) = self.chunks[self.find(offset)?];
+        Some(chunk[offset - start])
+    }
+
+    /// Returns the buffered input from `start` to `end`, or `None` if it isn't all buffered. The
+    /// range shares its chunk if it lies within one, and is copied otherwise.
+    fn slice(&self, start: usize, end: usize) -> Option<SharedBytes> {
+        if end > self.end {
+            return None;
+        }
+        if start == end {
+            return Some(SharedBytes::empty());
+        }
+        let i = self.find(start)?;
+        let (chunk_start, ref chunk) = self.chunks[i];
+        if end <= chunk_start + chunk.len() {
+            let mut range = SharedBytes::from(chunk.clone());
+            range.range(start - chunk_start)?;
+            return range.range(end - start);
+        }
+        let mut bytes = Vec::with_capacity(end - start);
+        for (offset, chunk) in self.chunks.iter().skip(i) {
+            if *offset >= end {
+                break;
+            }
+            let from = start.max(*offset) - offset;
+            let to = (end - offset).min(chunk.len());
+            bytes.extend_from_slice(&chunk[from..to]);
+        }
+        Some(SharedBytes::from(bytes))
+    }
+}
+
+/// BufferedStream is a byte `Stream` over any [`Read`], which reads input on demand.
+///
+/// Input is buffered as it is read, and discarded once every clone of the stream has moved past
+/// it. Cloning the stream (including [`TokenStream::backup`]) is O(1), as clones share the buffer,
+/// and a clone keeps the input after its position buffered for as long as it's alive, so it can
+/// always be restored.
+///
+/// A [checkpoint](TokenStream::checkpoint) doesn't keep the input buffered, so the checkpoint
+/// [`Parser::parse`](::Parser::parse) holds at the start of the input doesn't keep the whole
+/// input in memory. If parsing fails after the start of the input was discarded, the stream is
+/// returned where the error occurred rather than restored.
+///
+/// Input is read in chunks, and ranges within a chunk are [`SharedBytes`] slices of it. Ranges
+/// that span chunks are copied.
+///
+/// Errors from the reader end the input. They can be retrieved with
+/// [`BufferedStream::take_io_error`].
+pub struct BufferedStream<R: Read> {
+    buffer: Rc<RefCell<Buffer<R>>>,
+    offset: usize,
+    /// Whether the stream keeps the input after its offset buffered. Only checkpoints don't.
+    tracked: bool,
+}
+
+impl<R: Read> BufferedStream<R> {
+    /// Create a new `BufferedStream` reading from `reader`.
+    pub fn new(reader: R) -> Self {
+        let mut buffer = Buffer {
+            reader,
+            chunks: VecDeque::new(),
+            end: 0,
+            streams: BTreeMap::new(),
+            eof: false,
+            error: None,
+        };
+        buffer.track(0);
+        BufferedStream {
+            buffer: Rc::new(RefCell::new(buffer)),
+            offset: 0,
+            tracked: true,
+        }
+    }
+
+    /// Returns the number of bytes consumed from the input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Removes and returns the error that ended the input, if reading from the reader failed.
+    pub fn take_io_error(&self) -> Option<io::Error> {
+        self.buffer.borrow_mut().error.take()
+    }
+
+    fn seek(&mut self, offset: usize) {
+        if self.tracked {
+            let mut buffer = self.buffer.borrow_mut();
+            buffer.untrack(self.offset);
+            buffer.track(offset);
+        }
+        self.offset = offset;
+    }
+}
+
+impl<R: Read> Clone for BufferedStream<R> {
+    fn clone(&self) -> Self {
+        self.buffer.borrow_mut().track(self.offset);
+        BufferedStream {
+            buffer: self.buffer.clone(),
+            offset: self.offset,
+            tracked: true,
+        }
+    }
+}
+
+impl<R: Read> Drop for BufferedStream<R> {
+    fn drop(&mut self) {
+        if self.tracked {
+            self.buffer.borrow_mut().untrack(self.offset);
+        }
+    }
+}
+
+impl<R: Read> fmt::Debug for BufferedStream<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BufferedStream")
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+/// Streams are equal if they share a buffer and are at the same offset.
+impl<R: Read> PartialEq for BufferedStream<R> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.buffer, &other.buffer) && self.offset == other.offset
+    }
+}
+
+impl<R: Read> TokenStream for BufferedStream<R> {
+    type Stream = Self;
+    type Position = NullPosition;
+    type Item = u8;
+    type Range = SharedBytes;
+
+    fn peek(&self) -> Option<Self::Item> {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.fill(self.offset + 1);
+        buffer.get(self.offset)
+    }
+
+    fn pop(&mut self) -> Option<Self::Item> {
+        let item = self.peek();
+        if item.is_some() {
+            let offset = self.offset + 1;
+            self.seek(offset);
+        }
+        item
+    }
+
+    fn tokens(&self) -> Tokens<'_, Self::Item> {
+        // The input after the stream stays buffered while it's borrowed, so its chunks are
+        // iterated directly, looking each one up once.
+        let buffer = &self.buffer;
+        let mut offset = self.offset;
+        let mut chunk: Option<(usize, Arc<[u8]>)> = None;
+        Tokens::new(std::iter::from_fn(move || {
+            let (start, bytes) = match chunk {
+                Some((start, ref bytes)) if offset < start + bytes.len() => (start, bytes),
+                _ => {
+                    let mut buffer = buffer.borrow_mut();
+                    buffer.fill(offset + 1);
+                    let i = buffer.find(offset)?;
+                    let (start, ref bytes) = *chunk.insert(buffer.chunks[i].clone());
+                    (start, bytes)
+                }
+            };
+            let item = bytes[offset - start];
+            offset += 1;
+            Some(item)
+        }))
+    }
+
+    fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
+        let end = self.offset + to_idx;
+        let range = {
+            let mut buffer = self.buffer.borrow_mut();
+            buffer.fill(end);
+            buffer.slice(self.offset, end)
+        };
+        if range.is_some() {
+            self.seek(end);
+        }
+        range
+    }
+
+    /// Consumes and returns the input that's buffered after the stream, reading more only if none
+    /// is, rather than reading the rest of the input.
+    fn as_range(&mut self) -> Self::Range {
+        let (range, end) = {
+            let mut buffer = self.buffer.borrow_mut();
+            buffer.fill(self.offset + 1);
+            let end = buffer.end.max(self.offset);
+            (buffer.slice(self.offset, end), end)
+        };
+        match range {
+            Some(range) => {
+                self.seek(end);
+                range
+            }
+            None => SharedBytes::empty(),
+        }
+    }
+
+    fn position(&self) -> &Self::Position {
+        &NullPosition
+    }
+
+    fn distance(&self, end: &Self) -> usize {
+        end.offset
+            .checked_sub(self.offset)
+            .expect("distance to a stream behind this one")
+    }
+
+    fn checkpoint(&self) -> Self {
+        BufferedStream {
+            buffer: self.buffer.clone(),
+            offset: self.offset,
+            tracked: false,
+        }
+    }
+
+    fn restore_checkpoint(&mut self, checkpoint: Self) -> bool {
+        let buffered = {
+            let buffer = self.buffer.borrow();
+            let first = buffer
+                .chunks
+                .front()
+                .map_or(buffer.end, |&(start, _)| start);
+            checkpoint.offset >= first
+        };
+        if buffered {
+            self.seek(checkpoint.offset);
+        }
+        buffered
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::cell::Cell;
+
+    use super::*;
+    use error::{Error, Expected};
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::repeat::{many, many1};
+    use parser::Parser;
+    use stream::{LinePosition, State};
+
+    /// A reader that returns at most `n` bytes per read.
+    struct Trickle<'a> {
+        input: &'a [u8],
+        n: usize,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.n.min(buf.len()).min(self.input.len());
+            buf[..n].copy_from_slice(&self.input[..n]);
+            self.input = &self.input[n..];
+            Ok(n)
+        }
+    }
+
+    fn trickle(input: &str, n: usize) -> BufferedStream<Trickle<'_>> {
+        BufferedStream::new(Trickle {
+            input: input.as_bytes(),
+            n,
+        })
+    }
+
+    #[test]
+    fn test_buffered_stream() {
+        let mut stream = trickle("GET /index.html", 2);
+        assert_eq!(stream.peek(), Some(b'G'));
+        assert_eq!(stream.range(3), Some(SharedBytes::from("GET")));
+        assert_eq!(stream.pop(), Some(b' '));
+        assert_eq!(stream.offset(), 4);
+        assert_eq!(stream.range(20), None);
+        assert_eq!(stream.tokens().take(3).collect::<Vec<_>>(), b"/in");
+        // Only the buffered input is consumed.
+        assert_eq!(stream.as_range(), SharedBytes::from("/index.html"));
+        assert_eq!(stream.peek(), None);
+        assert_eq!(stream.offset(), 15);
+
+        let mut stream = trickle("GET /index.html", 4);
+        assert_eq!(stream.as_range(), SharedBytes::from("GET "));
+        assert_eq!(stream.as_range(), SharedBytes::from("/ind"));
+
+        let mut parser = range("GET ").with(many1::<Vec<_>, _>(item(b'/').or(ascii::letter())));
+        let (output, stream) = parser.parse(trickle("GET /index.html", 3)).unwrap();
+        assert_eq!(output, Some(b"/index".to_vec()));
+        assert_eq!(stream.offset(), 10);
+
+        let (error, stream) = parser.parse(trickle("GET ?", 8)).unwrap_err();
+        assert_eq!(
+            error,
+            Error::item(b'?').expected_one_of(vec![Expected::from(b'/'), "an ascii letter".into()])
+        );
+        assert_eq!(stream.offset(), 0);
+        // Once the start of the input has been discarded, it can't be restored.
+        let (_, stream) = parser.parse(trickle("GET ?", 1)).unwrap_err();
+        assert_eq!(stream.offset(), 4);
+    }
+
+    #[test]
+    fn test_buffered_stream_ranges() {
+        let mut stream = trickle("abcdef", 4);
+        let head = stream.range(2).unwrap();
+        // Ranges within a chunk share it, and ranges across chunks are copied.
+        let tail = stream.range(3).unwrap();
+        assert_eq!(head.as_ptr(), stream.buffer.borrow().chunks[0].1.as_ptr());
+        assert_eq!(
+            (head, tail),
+            (SharedBytes::from("ab"), SharedBytes::from("cde"))
+        );
+        assert_eq!(stream.range(0), Some(SharedBytes::from("")));
+    }
+
+    #[test]
+    fn test_buffered_stream_discard() {
+        let input = "ab\n".repeat(10_000);
+        let mut stream = BufferedStream::new(Trickle {
+            input: input.as_bytes(),
+            n: 100,
+        });
+        let backup = stream.backup();
+        for _ in 0..10_000 {
+            assert!(stream.range(3).is_some());
+        }
+        // The backup keeps the input after it buffered.
+        assert_eq!(stream.buffer.borrow().chunks.len(), 300);
+        stream.restore(backup);
+        assert_eq!(stream.peek(), Some(b'a'));
+
+        let mut parser = many::<Vec<_>, _>(range("ab\n"));
+        let (_, stream) = parser.parse_lazy(stream).unwrap();
+        assert_eq!(stream.offset(), 30_000);
+        // Once no stream is behind it, the input is discarded.
+        assert!(stream.buffer.borrow().chunks.len() <= 1);
+    }
+
+    #[test]
+    fn test_buffered_stream_state() {
+        let input = "ab\n".repeat(10_000) + "!";
+        let stream = BufferedStream::new(Trickle {
+            input: input.as_bytes(),
+            n: 100,
+        });
+        let mut parser = many::<Vec<_>, _>(range("ab\n")).skip(item(b'?'));
+        let (error, _) = parser
+            .parse(State::<_, LinePosition>::from(stream))
+            .unwrap_err();
+        assert_eq!(error, Error::item(b'!').expected_item(b'?').at((10_001, 1)));
+    }
+
+    #[test]
+    fn test_buffered_stream_parse() {
+        // `parse` doesn't keep the whole input buffered while it parses.
+        let input = "ab\n".repeat(100_000);
+        let stream = BufferedStream::new(Trickle {
+            input: input.as_bytes(),
+            n: 100,
+        });
+        let chunks = Cell::new(0);
+        let mut parser = many::<(), _>(range("ab\n").and_then(
+            |_, stream: BufferedStream<Trickle>| {
+                chunks.set(chunks.get().max(stream.buffer.borrow().chunks.len()));
+                stream.ok(())
+            },
+        ));
+        let (_, stream) = parser.parse(stream).unwrap();
+        assert_eq!(stream.offset(), 300_000);
+        assert!(chunks.get() <= 2);
+    }
+
+    #[test]
+    fn test_buffered_stream_backtrack() {
+        let input = "a".repeat(100_000);
+        let stream = BufferedStream::new(Trickle {
+            input: input.as_bytes(),
+            n: 100,
+        });
+        let mut parser = many::<Vec<_>, _>(item(b'a')).skip(item(b'b'));
+        // `parse` can't restore the start of the input once it has been discarded, so the stream
+        // is returned where parsing failed.
+        let (_, stream) = parser.parse(stream).unwrap_err();
+        assert_eq!(stream.offset(), 100_000);
+
+        // A backup keeps the input after it buffered, so it can be restored across many refills.
+        let mut stream = trickle(&input, 100);
+        let backup = stream.backup();
+        let (output, _) = many::<Vec<_>, _>(item(b'a')).parse_lazy(stream).unwrap();
+        assert_eq!(output.map(|a| a.len()), Some(100_000));
+        stream = backup;
+        assert_eq!(stream.offset(), 0);
+        assert_eq!(stream.tokens().take(200).count(), 200);
+    }
+
+    #[test]
+    fn test_buffered_stream_tokens() {
+        let stream = trickle("abcdef", 2);
+        assert_eq!(stream.tokens().collect::<Vec<_>>(), b"abcdef");
+        let mut stream = trickle("abcdef", 4);
+        stream.range(3);
+        assert_eq!(stream.tokens().collect::<Vec<_>>(), b"def");
+        // The remaining input stays buffered for the stream, however far tokens are read.
+        assert_eq!(stream.buffer.borrow().chunks.len(), 2);
+    }
+}
@@ -1,7 +1,14 @@
-use super::{NullPosition, RangeStream, Stream, StreamItem, Tokens};
+use super::{NullPosition, RangeStream, StreamItem, TextRange, Token, TokenStream, Tokens};
 
 macro_rules! impl_StreamItem {
-    ($T:ty) => {
+    ($T:ty, { $($extra:item)* }) => {
+        impl Token for $T {
+            fn to_char(&self) -> Option<char> {
+                Some(char::from(*self))
+            }
+            $($extra)*
+        }
+
         impl StreamItem for $T {
             fn is_ascii(&self) -> bool {
                 <$T>::is_ascii(self)
@@ -46,8 +53,39 @@ macro_rules! impl_StreamItem {
     };
 }
 
-impl_StreamItem!(char);
-impl_StreamItem!(u8);
+impl_StreamItem!(char, {
+    fn width(&self) -> usize {
+        self.len_utf8()
+    }
+    fn starts_char(&self) -> bool {
+        true
+    }
+    fn utf16_width(&self) -> usize {
+        self.len_utf16()
+    }
+});
+
+impl_StreamItem!(u8, {
+    fn width(&self) -> usize {
+        1
+    }
+    fn starts_char(&self) -> bool {
+        // UTF-8 continuation bytes have the form 0b10xxxxxx.
+        *self & 0xC0 != 0x80
+    }
+    fn utf16_width(&self) -> usize {
+        // Only characters encoded in four bytes, with a leading byte of the form 0b11110xxx,
+        // are outside the Basic Multilingual Plane and take two UTF-16 code units.
+        match *self {
+            b if b & 0xF8 == 0xF0 => 2,
+            b if b.starts_char() => 1,
+            _ => 0,
+        }
+    }
+    fn to_text<I: Iterator<Item = Self>>(tokens: I) -> Option<String> {
+        String::from_utf8(tokens.collect()).ok()
+    }
+});
 
 impl<'a> RangeStream for &'a str {
     fn empty() -> Self {
@@ -56,15 +94,18 @@ impl<'a> RangeStream for &'a str {
     fn len(&self) -> usize {
         <str>::len(self)
     }
-    fn from_str(s: &'static str) -> Self {
-        s
-    }
     fn into_string(self) -> Result<String, Self> {
         Ok(String::from(self))
     }
 }
 
-impl<'a> Stream for &'a str {
+impl TextRange for &str {
+    fn from_str(s: &'static str) -> Self {
+        s
+    }
+}
+
+impl<'a> TokenStream for &'a str {
     type Stream = Self;
     type Position = NullPosition;
     type Item = char;
@@ -91,7 +132,7 @@ impl<'a> Stream for &'a str {
     }
 
     fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
-        (to_idx <= self.len()).then(|| {
+        self.is_char_boundary(to_idx).then(|| {
             let (head, tail) = self.split_at(to_idx);
             *self = &tail;
             head
@@ -105,6 +146,10 @@ impl<'a> Stream for &'a str {
     fn position(&self) -> &Self::Position {
         &NullPosition
     }
+
+    fn distance(&self, end: &Self) -> usize {
+        self.len() - end.len()
+    }
 }
 
 impl<'a> RangeStream for &'a [u8] {
@@ -114,15 +159,18 @@ impl<'a> RangeStream for &'a [u8] {
     fn len(&self) -> usize {
         <[u8]>::len(self)
     }
-    fn from_str(s: &'static str) -> Self {
-        s.as_ref()
-    }
     fn into_string(self) -> Result<String, Self> {
         String::from_utf8(self.to_vec()).map_err(|_| self)
     }
 }
 
-impl<'a> Stream for &'a [u8] {
+impl TextRange for &[u8] {
+    fn from_str(s: &'static str) -> Self {
+        s.as_ref()
+    }
+}
+
+impl<'a> TokenStream for &'a [u8] {
     type Stream = Self;
     type Position = NullPosition;
     type Item = u8;
@@ -158,4 +206,8 @@ impl<'a> Stream for &'a [u8] {
     fn position(&self) -> &Self::Position {
         &NullPosition
     }
+
+    fn distance(&self, end: &Self) -> usize {
+        self.len() - end.len()
+    }
 }
@@ -1,25 +1,36 @@
 //! Traits and implementations defining parsable input streams.
 
+pub mod buffered;
 pub mod impls;
+pub mod partial;
 pub mod position;
+pub mod shared;
 pub mod state;
+pub mod token;
 
 use std::fmt::Debug;
 use std::option::Option::*;
 
-pub use self::position::{IndexPosition, LinePosition, NullPosition, Position};
+pub use self::buffered::BufferedStream;
+pub use self::partial::Partial;
+pub use self::position::{
+    ColumnPosition, ColumnUnit, FileId, FilePosition, IndexPosition, LineConfig, LineIndex,
+    LinePosition, NullPosition, Position, SourcePosition, Span, Spanned,
+};
+pub use self::shared::{SharedBytes, SharedStr};
 pub use self::state::State;
+pub use self::token::TokenSlice;
 use error::{Error, ParseResult};
 use traits::StrLike;
 
 /// SourceCode is a type alias for str `Stream` positioned by rows and columns.
 pub type SourceCode = State<&'static str, LinePosition>;
 
-/// IndexedStream is a type alias for `Stream` positioned by its index.
+/// IndexedStream is a type alias for `TokenStream` positioned by its index.
 pub type IndexedStream<S> = State<S, IndexPosition>;
 
-/// Tokens is an iterator over the tokens of some `Stream`.
-/// It is returned by the `tokens` method of `Stream`.
+/// Tokens is an iterator over the tokens of some `TokenStream`.
+/// It is returned by the `tokens` method of `TokenStream`.
 pub struct Tokens<'a, T>(Box<dyn Iterator<Item = T> + 'a>);
 
 impl<'a, T> Tokens<'a, T> {
@@ -36,7 +47,48 @@ impl<'a, T> Iterator for Tokens<'a, T> {
     }
 }
 
-pub trait StreamItem: Copy + PartialEq + Eq + Debug + From<u8> + Into<char> {
+/// Token is implemented by the items of every `TokenStream`.
+///
+/// Tokens needn't be characters, so the combinators can parse the output of a separate lexer:
+/// references to any `PartialEq + Debug` type are tokens (see [`TokenSlice`]), and other `Copy`
+/// types can implement `Token`. Its methods describe how a token relates to text; the defaults
+/// suit tokens that aren't characters.
+pub trait Token: Copy + PartialEq + Debug {
+    /// Returns the character the token represents, if any. Used to display tokens in errors, and
+    /// to find line breaks.
+    fn to_char(&self) -> Option<char> {
+        None
+    }
+
+    /// Returns the number of units the token occupies in the underlying input, as counted by
+    /// [`TokenStream::range`]: the UTF-8 length for `char`s and 1 for bytes and other tokens.
+    fn width(&self) -> usize {
+        1
+    }
+
+    /// Returns `true` if the token begins a character, i.e. it isn't a UTF-8 continuation byte.
+    fn starts_char(&self) -> bool {
+        true
+    }
+
+    /// Returns the number of UTF-16 code units the token contributes to its character: the
+    /// UTF-16 length for `char`s and the leading bytes of UTF-8 sequences, and 0 for continuation
+    /// bytes.
+    fn utf16_width(&self) -> usize {
+        1
+    }
+
+    /// Returns the text made up by `tokens`, or `None` if they aren't all characters. Used to
+    /// display ranges in errors.
+    fn to_text<I: Iterator<Item = Self>>(tokens: I) -> Option<String> {
+        tokens.map(|t| t.to_char()).collect()
+    }
+}
+
+impl<T: PartialEq + Debug> Token for &T {}
+
+/// StreamItem is implemented by the items of text streams: `char`s and bytes.
+pub trait StreamItem: Token + Eq + From<u8> + Into<char> {
     fn is_ascii(&self) -> bool;
     fn is_ascii_alphabetic(&self) -> bool;
     fn is_ascii_alphanumeric(&self) -> bool;
@@ -54,23 +106,53 @@ pub trait StreamItem: Copy + PartialEq + Eq + Debug + From<u8> + Into<char> {
     fn as_char(&self) -> char;
 }
 
-pub trait RangeStream: Stream + StrLike + PartialEq + Clone + Debug {
+pub trait RangeStream: TokenStream + PartialEq + Clone + Debug {
     fn empty() -> Self;
     fn len(&self) -> usize;
-    fn from_str(s: &'static str) -> Self;
     fn into_string(self) -> Result<String, Self>;
 }
 
-/// The Stream trait represents data that can be consumed by a `Parser`.
-pub trait Stream: Sized + Clone + Debug {
+/// TextRange is implemented by the ranges of text streams, which can be created from string
+/// literals.
+pub trait TextRange: RangeStream + StrLike {
+    fn from_str(s: &'static str) -> Self;
+}
+
+/// Stream is implemented by every [`TokenStream`] of characters or bytes, such as `&str`, `&[u8]`
+/// and `State`s wrapping them. Parsers of text, which match items against bytes and ranges against
+/// string literals, are generic over `S: Stream`. Its item and range types are constrained through
+/// `Char` and `Text`, as in `S: Stream<Char = char>`.
+pub trait Stream:
+    TokenStream<Item = <Self as Stream>::Char, Range = <Self as Stream>::Text>
+{
+    /// The stream's [`TokenStream::Item`].
+    type Char: StreamItem;
+    /// The stream's [`TokenStream::Range`].
+    type Text: TextRange<Item = Self::Char, Range = Self::Text>;
+}
+
+impl<S> Stream for S
+where
+    S: TokenStream,
+    S::Item: StreamItem,
+    S::Range: TextRange,
+{
+    type Char = S::Item;
+    type Text = S::Range;
+}
+
+/// The TokenStream trait represents data that can be consumed by a `Parser`: a sequence of
+/// [`Token`]s, which are characters or bytes for a text [`Stream`], or the output of a separate
+/// lexer for a [`TokenSlice`].
+pub trait TokenStream: Sized + Clone + Debug {
     /// The underlying Stream type.
-    type Stream: Stream<Item = Self::Item, Range = Self::Range>;
+    type Stream: TokenStream<Item = Self::Item, Range = Self::Range>;
 
     /// The Position type used to track the current parsing position.
     type Position: Position<Self::Stream>;
 
     /// The type of a single token.
-    type Item: StreamItem;
+    type Item: Token;
 
     /// The type of a range of tokens.
     type Range: RangeStream<Item = Self::Item, Range = Self::Range>;
@@ -95,9 +177,22 @@ pub trait Stream: Sized + Clone + Debug {
     /// Return the current position in the stream.
     fn position(&self) -> &Self::Position;
 
+    /// Returns the length of the input between this stream and `end`, a later state of the same
+    /// stream, in the units counted by [`TokenStream::range`]. This is the length of the range
+    /// consumed to get from `self` to `end`.
+    ///
+    /// By default this compares the widths of the remaining tokens of both streams, which is O(n).
+    /// Streams that know their offset into the input should override it.
+    fn distance(&self, end: &Self) -> usize {
+        let remaining = |stream: &Self| stream.tokens().map(|t| t.width()).sum::<usize>();
+        remaining(self) - remaining(end)
+    }
+
     fn nth_position(&self, n: usize) -> Self::Position {
         let mut pos = self.position().clone();
-        self.tokens().take(n).for_each(|t| pos.update(&t));
+        let mut tokens = self.tokens();
+        tokens.by_ref().take(n).for_each(|t| pos.update(&t));
+        pos.followed_by(tokens.next().as_ref());
         pos
     }
 
@@ -113,6 +208,22 @@ pub trait Stream: Sized + Clone + Debug {
         *self = backup;
     }
 
+    /// Return a snapshot of the current stream for [`Parser::parse`](::Parser::parse) to restore
+    /// if parsing fails. Unlike a [`backup`](TokenStream::backup), a checkpoint needn't keep the
+    /// input after it available, so streams that discard consumed input can do so while it's
+    /// held. By default this is `backup()`.
+    fn checkpoint(&self) -> Self {
+        self.backup()
+    }
+
+    /// Reset the stream to `checkpoint`, returned by [`TokenStream::checkpoint`], if the input
+    /// after it is still available. Returns whether the stream was reset; if not, it is left
+    /// where it is.
+    fn restore_checkpoint(&mut self, checkpoint: Self) -> bool {
+        self.restore(checkpoint);
+        true
+    }
+
     fn result<O>(self, result: Option<O>) -> ParseResult<Self, O> {
         Ok((result, self))
     }
@@ -138,11 +249,57 @@ pub trait Stream: Sized + Clone + Debug {
         self.err_at(position, error)
     }
 
+    /// Returns `true` if the stream holds only the input available so far, and more may follow.
+    ///
+    /// Parsers that reach the end of a partial stream fail with [`Error::incomplete`] rather than
+    /// [`Error::eoi`]. By default, streams are not partial; see [`Partial`].
+    fn is_partial(&self) -> bool {
+        false
+    }
+
+    /// Create an `Error` for reaching the end of the stream: [`Error::incomplete`] if the stream
+    /// [is partial](TokenStream::is_partial), or [`Error::eoi`] otherwise.
+    fn end_of_input(&self) -> Error<Self> {
+        if self.is_partial() {
+            Error::incomplete()
+        } else {
+            Error::eoi()
+        }
+    }
+
     fn new_error(&self) -> Error<Self> {
         let error = match self.peek() {
             Some(item) => Error::item(item),
-            None => Error::eoi(),
+            None => self.end_of_input(),
         };
         error.at(self.position().clone())
     }
 }
+
+/// RecoverStream is implemented by streams that record the errors parsers recover from, so that
+/// parsing can continue past them and report every error at the end. See [`State`], and
+/// [`Parser::recover_with`](::Parser::recover_with).
+pub trait RecoverStream: TokenStream {
+    /// Record a recoverable `error`.
+    fn record_error(&mut self, error: Error<Self>);
+
+    /// Removes and returns the errors recorded with [`RecoverStream::record_error`], in the order
+    /// they were recorded.
+    fn take_errors(&mut self) -> Vec<Error<Self>>;
+}
+
+/// StateStream is implemented by streams carrying user-defined state, such as a symbol table or a
+/// nesting depth, which parsers can read and update while parsing.
+///
+/// The state is part of the stream, so it's restored along with the rest of the stream when
+/// parsing backtracks. See [`State`], and the parsers in [`parser::state`](::parser::state).
+pub trait StateStream: TokenStream {
+    /// The type of the user state.
+    type State;
+
+    /// Returns the user state.
+    fn state(&self) -> &Self::State;
+
+    /// Returns the user state mutably.
+    fn state_mut(&mut self) -> &mut Self::State;
+}
@@ -0,0 +1,164 @@
+use super::{StateStream, TokenStream, Tokens};
+
+/// Partial wraps a `Stream` holding only the input received so far, such as the bytes read from a
+/// socket.
+///
+/// Parsers that reach the end of a `Partial` stream fail with an [incomplete](::Error::incomplete)
+/// error rather than an end-of-input error, and repeating parsers such as
+/// [`many`](::parser::repeat::many) fail instead of stopping, since more input might continue the
+/// repetition. When parsing fails with an error that [`is_incomplete`](::Error::is_incomplete),
+/// parsing can be retried from the start once more input is available.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Partial<S: TokenStream> {
+    pub stream: S,
+}
+
+impl<S: TokenStream> Partial<S> {
+    pub fn new(stream: S) -> Self {
+        Partial { stream }
+    }
+
+    /// Unwraps the inner stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S: TokenStream> From<S> for Partial<S> {
+    fn from(stream: S) -> Self {
+        Partial::new(stream)
+    }
+}
+
+impl<S: TokenStream> TokenStream for Partial<S> {
+    type Stream = S::Stream;
+    type Position = S::Position;
+    type Item = S::Item;
+    type Range = S::Range;
+
+    fn peek(&self) -> Option<Self::Item> {
+        self.stream.peek()
+    }
+
+    fn pop(&mut self) -> Option<Self::Item> {
+        self.stream.pop()
+    }
+
+    fn tokens(&self) -> Tokens<'_, Self::Item> {
+        self.stream.tokens()
+    }
+
+    fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
+        self.stream.range(to_idx)
+    }
+
+    fn as_range(&mut self) -> Self::Range {
+        self.stream.as_range()
+    }
+
+    fn position(&self) -> &Self::Position {
+        self.stream.position()
+    }
+
+    fn distance(&self, end: &Self) -> usize {
+        self.stream.distance(&end.stream)
+    }
+
+    fn is_partial(&self) -> bool {
+        true
+    }
+
+    fn checkpoint(&self) -> Self {
+        Partial::new(self.stream.checkpoint())
+    }
+
+    fn restore_checkpoint(&mut self, checkpoint: Self) -> bool {
+        self.stream.restore_checkpoint(checkpoint.stream)
+    }
+}
+
+impl<S: StateStream> StateStream for Partial<S> {
+    type State = S::State;
+
+    fn state(&self) -> &Self::State {
+        self.stream.state()
+    }
+
+    fn state_mut(&mut self) -> &mut Self::State {
+        self.stream.state_mut()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::{Error, Info};
+    use parser::choice::optional;
+    use parser::item::{any, ascii, eoi_, item};
+    use parser::range::range;
+    use parser::repeat::{many, sep_by, take_until};
+    use parser::Parser;
+    use stream::{IndexPosition, State};
+
+    #[test]
+    fn test_partial() {
+        test_parser!(Partial<&str> => char | item(b'a'), {
+            "ab" => ok('a', "b"),
+            "" => err(Error::incomplete().expected_item('a')),
+        });
+
+        test_parser!(Partial<&str> => &str | range("GET"), {
+            "GET /" => ok("GET", " /"),
+            "GE" => err(Error::incomplete().expected_range("GET")),
+            "GX" => err(Error::item('X').expected_range("GET")),
+        });
+
+        test_parser!(Partial<&str> => () | eoi_(), {
+            "" => err(Error::incomplete().expected(Info::EOI)),
+        });
+
+        test_parser!(Partial<&str> => char | optional(item(b'a')), {
+            "b" => noop(()),
+            "" => err(Error::incomplete().expected_item('a')),
+        });
+
+        let methods = || vec![Info::Range("GET"), Info::Range("GIVE")];
+        test_parser!(Partial<&str> => &str | choice![range("GET"), range("GIVE")], {
+            "G" => err(Error::incomplete().expected_one_of(methods())),
+            "GX" => err(Error::item('X').expected_one_of(methods())),
+        });
+    }
+
+    #[test]
+    fn test_partial_repeat() {
+        test_parser!(Partial<&str> => String | many::<String, _>(item(b'a')), {
+            "aab" => ok("aa".to_string(), "b"),
+            "aa" => err(Error::incomplete().expected_item('a')),
+        });
+
+        test_parser!(Partial<&str> => String | sep_by::<String, _, _>(ascii::letter(), item(b',')), {
+            "a,b;" => ok("ab".to_string(), ";"),
+            "a,b" => err(Error::incomplete().expected_item(',')),
+            "a," => err(Error::incomplete().expected("an ascii letter")),
+        });
+
+        test_parser!(Partial<&str> => String | take_until::<String, _, _>(any(), range("\r\n")), {
+            "ab\r\n" => ok("ab".to_string(), "\r\n"),
+            "ab\r" => err(
+                Error::incomplete().expected_one_of(vec![Info::from("a token"), Info::Range("\r\n")])
+            ),
+        });
+
+        let mut parser = many::<String, _>(item(b'a')).skip(item(b';'));
+        let input = State::<_, IndexPosition>::from(Partial::new("aa"));
+        let (error, _) = parser.parse(input).unwrap_err();
+        assert!(error.is_incomplete());
+        assert_eq!(error.position, 2.into());
+
+        let input = State::<_, IndexPosition>::from(Partial::new("aa;"));
+        assert_eq!(
+            parser.parse(input).map(|(output, _)| output),
+            Ok(Some("aa".to_string()))
+        );
+    }
+}
@@ -1,13 +1,19 @@
 //! Traits and implementations representing positions in an `Stream` stream.
 
+use std::cmp::Ordering;
 use std::fmt::{self, Debug, Display};
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::Deref;
 
-use super::{RangeStream, Stream};
+use serde_json::Value as Json;
+
+use super::{RangeStream, Token, TokenStream};
+use traits::AsBytes;
 
 /// The Position trait defines types that keep track of the cursor position while parsing an
-/// `Stream` stream.
-pub trait Position<S: Stream>:
+/// `TokenStream` stream.
+pub trait Position<S: TokenStream>:
     Default + Debug + Display + Clone + Ord + PartialEq + Eq + Hash
 {
     type Value: Ord;
@@ -16,13 +22,114 @@ pub trait Position<S: Stream>:
     fn update(&mut self, item: &S::Item);
     fn update_range(&mut self, range: &S::Range);
 
+    /// Called after the position is updated, with the item that follows it in the input, or
+    /// `None` at the end of the input. Positions that count an item differently depending on the
+    /// item after it, such as [`LinePosition`] with [`LineConfig::crlf`], resolve it here.
+    fn followed_by(&mut self, _next: Option<&S::Item>) {}
+
     fn fmt_msg(&self, msg: &str) -> String {
         format!("{} at {}", msg, self)
     }
+
+    /// Returns the file this position is in, or `None` if the position doesn't identify files.
+    /// See [`SourceMap`](::source_map::SourceMap).
+    fn file(&self) -> Option<FileId> {
+        None
+    }
+
+    /// Locates this position in `source`, the input it was produced from, returning its offset
+    /// in bytes. Returns `None` if the position doesn't carry enough information to be located.
+    fn offset_in(&self, _source: &[u8]) -> Option<usize> {
+        None
+    }
+
+    /// Returns a machine-readable representation of this position, used by
+    /// [`Error::to_json`](::error::Error::to_json). By default this is the `Display` string.
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+/// ColumnPosition is implemented by positions that track the column within a line, which
+/// indentation-sensitive parsers such as [`indented_block`](::parser::indent::indented_block)
+/// require.
+pub trait ColumnPosition<S: TokenStream>: Position<S> {
+    /// Returns the column of this position, counted from 1.
+    fn column(&self) -> u32;
+}
+
+/// A region of input, from `start` (inclusive) to `end` (exclusive).
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Span<P> {
+    pub start: P,
+    pub end: P,
+}
+
+impl<P> Span<P> {
+    /// Create a new `Span` from `start` to `end`.
+    pub fn new(start: P, end: P) -> Self {
+        Span { start, end }
+    }
+}
+
+impl<P: Display> Display for Span<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} to {}", self.start, self.end)
+    }
+}
+
+impl<P, T: Into<P>> From<(T, T)> for Span<P> {
+    fn from((start, end): (T, T)) -> Self {
+        Span::new(start.into(), end.into())
+    }
+}
+
+/// A value annotated with the [`Span`] of input it was parsed from, such as a node of a syntax
+/// tree. See [`Parser::spanned`](::Parser::spanned).
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct Spanned<T, P> {
+    pub value: T,
+    pub span: Span<P>,
+}
+
+impl<T, P> Spanned<T, P> {
+    pub fn new<I: Into<Span<P>>>(value: T, span: I) -> Self {
+        Spanned {
+            value,
+            span: span.into(),
+        }
+    }
+
+    /// Applies `f` to the value, keeping the span.
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Spanned<U, P> {
+        Spanned {
+            value: f(self.value),
+            span: self.span,
+        }
+    }
+
+    /// Unwraps the value, discarding the span.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T, P> Deref for Spanned<T, P> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Display, P: Display> Display for Spanned<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.value, self.span)
+    }
 }
 
 /// NullPosition is a dummy `Position` for streams that don't keep track of their current position.
-/// This is provided so that primitive types such as `&str` can implement `Stream`.
+/// This is provided so that primitive types such as `&str` can implement `TokenStream`.
 #[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct NullPosition;
 
@@ -32,12 +139,10 @@ impl Display for NullPosition {
     }
 }
 
-impl<S: Stream> Position<S> for NullPosition {
+impl<S: TokenStream> Position<S> for NullPosition {
     type Value = ();
 
-    fn value(&self) -> Self::Value {
-        ()
-    }
+    fn value(&self) -> Self::Value {}
 
     fn update(&mut self, _: &S::Item) {}
     fn update_range(&mut self, _: &S::Range) {}
@@ -45,39 +150,48 @@ impl<S: Stream> Position<S> for NullPosition {
     fn fmt_msg(&self, msg: &str) -> String {
         String::from(msg)
     }
+
+    fn to_json(&self) -> Json {
+        Json::Null
+    }
 }
 
 /// IndexPosition is a `Position` which is represented as an index.
 /// This is useful for binary data or any kind of virtual input stream.
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+///
+/// The index counts the same units as [`TokenStream::range`]: bytes for `&str` and `&[u8]`, and
+/// tokens for other streams. On text, it is a byte offset that can be passed to [`LineIndex`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub struct IndexPosition(usize);
 
-impl Default for IndexPosition {
-    fn default() -> Self {
-        IndexPosition(0)
-    }
-}
-
 impl Display for IndexPosition {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "index {}", self.0)
     }
 }
 
-impl<S: Stream> Position<S> for IndexPosition {
+impl<S: TokenStream> Position<S> for IndexPosition {
     type Value = usize;
 
     fn value(&self) -> Self::Value {
         self.0
     }
 
-    fn update(&mut self, _: &S::Item) {
-        self.0 += 1;
+    fn update(&mut self, item: &S::Item) {
+        self.0 += item.width();
     }
 
     fn update_range(&mut self, range: &S::Range) {
         self.0 += range.len();
     }
+
+    fn offset_in(&self, source: &[u8]) -> Option<usize> {
+        Some(self.0.min(source.len()))
+    }
+
+    fn to_json(&self) -> Json {
+        json!({ "offset": self.0 })
+    }
 }
 
 impl From<usize> for IndexPosition {
@@ -86,23 +200,155 @@ impl From<usize> for IndexPosition {
     }
 }
 
-impl From<LinePosition> for IndexPosition {
-    fn from(LinePosition { line, column }: LinePosition) -> Self {
-        IndexPosition((line * column) as usize)
+/// ColumnUnit is the unit [`LinePosition`] counts columns in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    /// Unicode characters, as most editors display columns.
+    #[default]
+    Chars,
+    /// Bytes of UTF-8.
+    Bytes,
+    /// UTF-16 code units, as used by the Language Server Protocol.
+    Utf16,
+}
+
+/// LineConfig configures how [`LinePosition`] counts columns and line breaks.
+///
+/// By default, every character occupies a single column, including tabs and the `\r` of a `\r\n`
+/// line break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LineConfig {
+    tab_width: u32,
+    crlf: bool,
+    unit: ColumnUnit,
+}
+
+impl Default for LineConfig {
+    fn default() -> Self {
+        LineConfig {
+            tab_width: 1,
+            crlf: false,
+            unit: ColumnUnit::Chars,
+        }
+    }
+}
+
+impl LineConfig {
+    pub fn new() -> Self {
+        LineConfig::default()
+    }
+
+    /// Sets the width of tab stops. A tab advances the column to the next tab stop, so with a
+    /// width of 4, a tab at column 1 or 3 advances it to column 5. Chainable.
+    pub fn tab_width(mut self, width: u32) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// Sets whether `\r\n` is treated as a single line break, so the `\r` doesn't occupy a
+    /// column. A `\r` that isn't followed by `\n` still occupies one. Chainable.
+    pub fn crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    /// Sets the unit columns are counted in. Chainable.
+    pub fn unit(mut self, unit: ColumnUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Returns `true` if `item`, followed by `next`, doesn't occupy a column.
+    fn is_crlf<T: Token>(&self, item: &T, next: Option<&T>) -> bool {
+        self.crlf
+            && item.to_char() == Some('\r')
+            && next.and_then(|next| next.to_char()) == Some('\n')
+    }
+
+    /// Returns the column following `item`, which is at `column` and followed by `next`.
+    fn advance<T: Token>(&self, column: u32, item: &T, next: Option<&T>) -> u32 {
+        match item.to_char() {
+            Some('\t') if self.tab_width > 1 => {
+                column + self.tab_width - (column - 1) % self.tab_width
+            }
+            _ if self.is_crlf(item, next) => column,
+            _ => {
+                let width = match self.unit {
+                    ColumnUnit::Chars => item.starts_char() as usize,
+                    ColumnUnit::Bytes => item.width(),
+                    ColumnUnit::Utf16 => item.utf16_width(),
+                };
+                column + width as u32
+            }
+        }
     }
 }
 
 /// LinePosition is a `Position` which is represented as a line number and column number.
 /// This is primarily useful for parsing text files or anything that has multiple lines.
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
+///
+/// Columns are counted as set by its [`LineConfig`], which is carried along with the position
+/// but isn't compared or hashed. A stream can be given a configured position with
+/// [`State::new`](super::State::new):
+///
+/// ```
+/// # extern crate rparse;
+/// # use rparse::stream::{LineConfig, LinePosition, State};
+/// # fn main() {
+/// let config = LineConfig::new().tab_width(4).crlf(true);
+/// let stream = State::<&str, LinePosition>::new("\tx\r\n", LinePosition::with_config(config));
+/// # }
+/// ```
+#[derive(Debug, Clone)]
 pub struct LinePosition {
     pub line: u32,
     pub column: u32,
+    pub config: LineConfig,
+    /// Whether the last item was a `\r` whose column depends on the item that follows it.
+    cr: bool,
+}
+
+impl LinePosition {
+    /// Create a `LinePosition` at the start of the input, counting columns as set by `config`.
+    pub fn with_config(config: LineConfig) -> Self {
+        LinePosition {
+            line: 1,
+            column: 1,
+            config,
+            cr: false,
+        }
+    }
 }
 
 impl Default for LinePosition {
     fn default() -> Self {
-        LinePosition { line: 1, column: 1 }
+        LinePosition::with_config(LineConfig::default())
+    }
+}
+
+impl PartialEq for LinePosition {
+    fn eq(&self, other: &Self) -> bool {
+        (self.line, self.column) == (other.line, other.column)
+    }
+}
+
+impl Eq for LinePosition {}
+
+impl PartialOrd for LinePosition {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LinePosition {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.line, self.column).cmp(&(other.line, other.column))
+    }
+}
+
+impl Hash for LinePosition {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.line, self.column).hash(state)
     }
 }
 
@@ -112,31 +358,584 @@ impl Display for LinePosition {
     }
 }
 
-impl<'a> Position<&'a str> for LinePosition {
+impl<S: TokenStream> Position<S> for LinePosition {
     type Value = (u32, u32);
 
     fn value(&self) -> Self::Value {
         (self.line, self.column)
     }
 
-    fn update(&mut self, item: &char) {
-        if let &'\n' = item {
+    fn update(&mut self, item: &S::Item) {
+        <Self as Position<S>>::followed_by(self, Some(item));
+        if item.to_char() == Some('\n') {
             self.line += 1;
             self.column = 1;
+        } else if self.config.crlf && item.to_char() == Some('\r') {
+            self.cr = true;
         } else {
+            self.column = self.config.advance(self.column, item, None);
+        }
+    }
+
+    fn update_range(&mut self, range: &S::Range) {
+        for item in range.tokens() {
+            <Self as Position<S>>::update(self, &item);
+        }
+    }
+
+    fn followed_by(&mut self, next: Option<&S::Item>) {
+        // A `\r` occupies a column unless the item after it ends the line.
+        if mem::replace(&mut self.cr, false) && next.and_then(|next| next.to_char()) != Some('\n') {
             self.column += 1;
         }
     }
 
-    fn update_range(&mut self, range: &&str) {
-        for item in range.chars() {
-            self.update(&item);
+    fn offset_in(&self, source: &[u8]) -> Option<usize> {
+        let mut offset = 0;
+        for _ in 1..self.line {
+            offset += source[offset..].iter().position(|&b| b == b'\n')? + 1;
+        }
+        let mut column = 1;
+        for (i, b) in source[offset..].iter().enumerate() {
+            if *b == b'\n' || (b.starts_char() && column >= self.column) {
+                return Some(offset + i);
+            }
+            column = self.config.advance(column, b, source.get(offset + i + 1));
         }
+        Some(source.len())
+    }
+
+    fn to_json(&self) -> Json {
+        json!({ "line": self.line, "column": self.column })
+    }
+}
+
+impl<S: TokenStream> ColumnPosition<S> for LinePosition {
+    fn column(&self) -> u32 {
+        self.column
     }
 }
 
 impl From<(u32, u32)> for LinePosition {
     fn from((line, column): (u32, u32)) -> Self {
-        LinePosition { line, column }
+        LinePosition {
+            line,
+            column,
+            config: LineConfig::default(),
+            cr: false,
+        }
+    }
+}
+
+/// SourcePosition is a `Position` which tracks the offset into the input alongside the line and
+/// column numbers. The offset is counted in the same units as [`TokenStream::range`] (bytes, for
+/// `&str` and `&[u8]`), while columns are counted in characters regardless of the stream's item
+/// type.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct SourcePosition {
+    pub offset: usize,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl Default for SourcePosition {
+    fn default() -> Self {
+        SourcePosition {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Display for SourcePosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, col {}", self.line, self.column)
+    }
+}
+
+impl<S: TokenStream> Position<S> for SourcePosition {
+    type Value = (usize, u32, u32);
+
+    fn value(&self) -> Self::Value {
+        (self.offset, self.line, self.column)
+    }
+
+    fn update(&mut self, item: &S::Item) {
+        self.offset += item.width();
+        if item.to_char() == Some('\n') {
+            self.line += 1;
+            self.column = 1;
+        } else if item.starts_char() {
+            self.column += 1;
+        }
+    }
+
+    fn update_range(&mut self, range: &S::Range) {
+        for item in range.tokens() {
+            <Self as Position<S>>::update(self, &item);
+        }
+    }
+
+    fn offset_in(&self, source: &[u8]) -> Option<usize> {
+        Some(self.offset.min(source.len()))
+    }
+
+    fn to_json(&self) -> Json {
+        json!({ "offset": self.offset, "line": self.line, "column": self.column })
+    }
+}
+
+impl<S: TokenStream> ColumnPosition<S> for SourcePosition {
+    fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl From<(usize, u32, u32)> for SourcePosition {
+    fn from((offset, line, column): (usize, u32, u32)) -> Self {
+        SourcePosition {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl From<SourcePosition> for LinePosition {
+    fn from(SourcePosition { line, column, .. }: SourcePosition) -> Self {
+        LinePosition::from((line, column))
+    }
+}
+
+impl From<SourcePosition> for IndexPosition {
+    fn from(SourcePosition { offset, .. }: SourcePosition) -> Self {
+        IndexPosition(offset)
+    }
+}
+
+/// LineIndex converts between byte offsets into a source text and line and column numbers.
+///
+/// The index holds the offset of the start of each line, so that the line containing an offset,
+/// or the start of a line, can be found in O(log n). Columns are counted within the line as set
+/// by a [`LineConfig`], as [`LinePosition`] counts them while parsing.
+///
+/// This converts positions from streams that track only an offset, such as
+/// [`IndexPosition`], into line and column numbers and back. Streams positioned by
+/// [`SourcePosition`] carry both, and need no conversion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex<'a> {
+    source: &'a [u8],
+    line_starts: Vec<usize>,
+    config: LineConfig,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Create a `LineIndex` for `source`.
+    pub fn new<T: AsBytes + ?Sized>(source: &'a T) -> Self {
+        let source = source.as_bytes();
+        let line_starts = Some(0)
+            .into_iter()
+            .chain(
+                source
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &b)| b == b'\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+        LineIndex {
+            source,
+            line_starts,
+            config: LineConfig::default(),
+        }
+    }
+
+    /// Sets how columns are counted. Chainable.
+    pub fn config(mut self, config: LineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Returns the number of lines in the source, counting the empty line after a trailing line
+    /// break.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the offset of the start of `line`, counted from 1.
+    pub fn line_start(&self, line: u32) -> Option<usize> {
+        let index = (line as usize).checked_sub(1)?;
+        self.line_starts.get(index).cloned()
+    }
+
+    /// Returns the offset of the end of `line`, i.e. of its line break, or the end of the source
+    /// for the last line.
+    fn line_end(&self, line: u32) -> usize {
+        self.line_start(line + 1)
+            .map_or(self.source.len(), |start| start - 1)
+    }
+
+    /// Returns the position of `offset`, or `None` if it's beyond the end of the source.
+    pub fn position(&self, offset: usize) -> Option<SourcePosition> {
+        if offset > self.source.len() {
+            return None;
+        }
+        let line = self.line_starts.partition_point(|&start| start <= offset) as u32;
+        let start = self.line_starts[line as usize - 1];
+        let column = (start..offset).fold(1, |column, i| {
+            self.config
+                .advance(column, &self.source[i], self.source.get(i + 1))
+        });
+        Some(SourcePosition {
+            offset,
+            line,
+            column,
+        })
+    }
+
+    /// Returns the position of `offset` as a `LinePosition` with this index's config, or `None`
+    /// if it's beyond the end of the source.
+    pub fn line_position(&self, offset: usize) -> Option<LinePosition> {
+        self.position(offset).map(|position| LinePosition {
+            config: self.config,
+            ..position.into()
+        })
+    }
+
+    /// Returns the offset of `position`, or `None` if it isn't in the source, e.g. its column is
+    /// beyond the end of its line or within a tab.
+    pub fn offset<P: Into<LinePosition>>(&self, position: P) -> Option<usize> {
+        let LinePosition { line, column, .. } = position.into();
+        let start = self.line_start(line)?;
+        let end = self.line_end(line);
+        let mut current = 1;
+        for (i, b) in self.source[start..end].iter().enumerate() {
+            if b.starts_char() && current >= column {
+                return (current == column).then(|| start + i);
+            }
+            current = self
+                .config
+                .advance(current, b, self.source.get(start + i + 1));
+        }
+        (current == column).then_some(end)
+    }
+}
+
+/// FileId identifies a source file, usually one registered in a
+/// [`SourceMap`](::source_map::SourceMap).
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct FileId(u32);
+
+impl FileId {
+    /// Returns the index of the file, counted from 0.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "file #{}", self.0)
+    }
+}
+
+impl From<u32> for FileId {
+    fn from(x: u32) -> Self {
+        FileId(x)
+    }
+}
+
+/// FilePosition is a `Position` which tracks the file being parsed alongside a
+/// [`SourcePosition`] within it. This is useful for parsing input spread across several files,
+/// which are registered in a [`SourceMap`](::source_map::SourceMap) to resolve positions back to
+/// the file's name and source text.
+///
+/// A `FilePosition` can be created from a [`FileId`], for the start of the file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct FilePosition {
+    pub file: FileId,
+    pub position: SourcePosition,
+}
+
+impl Deref for FilePosition {
+    type Target = SourcePosition;
+
+    fn deref(&self) -> &SourcePosition {
+        &self.position
+    }
+}
+
+impl Display for FilePosition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.file, self.position)
+    }
+}
+
+impl<S: TokenStream> Position<S> for FilePosition {
+    type Value = (FileId, <SourcePosition as Position<S>>::Value);
+
+    fn value(&self) -> Self::Value {
+        (
+            self.file,
+            <SourcePosition as Position<S>>::value(&self.position),
+        )
+    }
+
+    fn update(&mut self, item: &S::Item) {
+        <SourcePosition as Position<S>>::update(&mut self.position, item)
+    }
+
+    fn update_range(&mut self, range: &S::Range) {
+        <SourcePosition as Position<S>>::update_range(&mut self.position, range)
+    }
+
+    fn followed_by(&mut self, next: Option<&S::Item>) {
+        <SourcePosition as Position<S>>::followed_by(&mut self.position, next)
+    }
+
+    fn file(&self) -> Option<FileId> {
+        Some(self.file)
+    }
+
+    fn offset_in(&self, source: &[u8]) -> Option<usize> {
+        <SourcePosition as Position<S>>::offset_in(&self.position, source)
+    }
+
+    fn to_json(&self) -> Json {
+        let mut json = <SourcePosition as Position<S>>::to_json(&self.position);
+        json["file"] = json!(self.file.0);
+        json
+    }
+}
+
+impl<S: TokenStream> ColumnPosition<S> for FilePosition {
+    fn column(&self) -> u32 {
+        <SourcePosition as ColumnPosition<S>>::column(&self.position)
+    }
+}
+
+impl From<FileId> for FilePosition {
+    fn from(file: FileId) -> Self {
+        FilePosition {
+            file,
+            position: SourcePosition::default(),
+        }
+    }
+}
+
+impl<F: Into<FileId>> From<(F, usize, u32, u32)> for FilePosition {
+    fn from((file, offset, line, column): (F, usize, u32, u32)) -> Self {
+        FilePosition {
+            file: file.into(),
+            position: SourcePosition::from((offset, line, column)),
+        }
+    }
+}
+
+impl From<FilePosition> for SourcePosition {
+    fn from(FilePosition { position, .. }: FilePosition) -> Self {
+        position
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use parser::item::{any, item};
+    use parser::range::range;
+    use parser::repeat::many;
+    use parser::Parser;
+    use stream::{State, Stream};
+
+    #[test]
+    fn test_line_position_bytes() {
+        let mut parser = many::<Vec<_>, _>(any()).skip(item(b'!'));
+        test_parser!(State<&[u8], LinePosition> => Vec<u8> | parser, {
+            &b""[..] => err(Error::eoi().expected_item(b'!').at((1, 1))),
+        });
+
+        let mut parser = range("ab\nc");
+        test_parser!(State<&[u8], LinePosition> => &[u8] | parser, {
+            &b"ab\ncd"[..] => ok(&b"ab\nc"[..], (&b"d"[..], (2, 2))),
+            &b"ab\nx"[..] => err(
+                Error::item(b'x')
+                    .expected_range(&b"ab\nc"[..])
+                    .at((2, 1))
+                    .spanning(((1, 1), (2, 2)))
+            ),
+        });
+
+        // multi-byte characters occupy a single column
+        let mut parser = range("\u{e9}t\u{e9}");
+        test_parser!(State<&[u8], LinePosition> => &[u8] | parser, {
+            "\u{e9}t\u{e9}!".as_bytes() => ok("\u{e9}t\u{e9}".as_bytes(), (&b"!"[..], (1, 4))),
+        });
+    }
+
+    #[test]
+    fn test_line_config() {
+        fn end<S: TokenStream>(stream: S, config: LineConfig) -> (u32, u32) {
+            let mut stream =
+                State::<S, LinePosition>::new(stream, LinePosition::with_config(config));
+            stream.as_range();
+            (stream.position.line, stream.position.column)
+        }
+
+        let tabs = LineConfig::new().tab_width(4);
+        assert_eq!(end("\tx", LineConfig::new()), (1, 3));
+        assert_eq!(end("\tx", tabs), (1, 6));
+        assert_eq!(end("ab\t\tx", tabs), (1, 10));
+        assert_eq!(end("abcd\tx".as_bytes(), tabs), (1, 10));
+
+        let crlf = LineConfig::new().crlf(true);
+        assert_eq!(end("ab\r", LineConfig::new()), (1, 4));
+        assert_eq!(end("ab\r\ncd", crlf), (2, 3));
+        // Only a `\r` followed by `\n` is zero-width.
+        assert_eq!(end("ab\r", crlf), (1, 4));
+        assert_eq!(end("a\rb", crlf), (1, 4));
+        fn error_at(input: &'static str) -> LinePosition {
+            let config = LineConfig::new().crlf(true);
+            let stream = State::<&str, LinePosition>::new(input, LinePosition::with_config(config));
+            let mut parser = many::<String, _>(item(b'a').or(item(b'\r'))).skip(item(b'!'));
+            parser.parse(stream).unwrap_err().0.position
+        }
+        assert_eq!(error_at("a\r\n"), LinePosition::from((1, 2)));
+        assert_eq!(error_at("a\rb"), LinePosition::from((1, 3)));
+        assert_eq!(error_at("a\r\ra\n"), LinePosition::from((1, 5)));
+        let index = LineIndex::new("a\r\rb\r\n").config(crlf);
+        assert_eq!(index.line_position(4), Some(LinePosition::from((1, 5))));
+        assert_eq!(index.line_position(5), Some(LinePosition::from((1, 5))));
+        assert_eq!(index.offset((1, 5)), Some(4));
+
+        let input = "\u{e9}\u{1f600}x";
+        for &(unit, column) in &[
+            (ColumnUnit::Chars, 4),
+            (ColumnUnit::Bytes, 8),
+            (ColumnUnit::Utf16, 5),
+        ] {
+            let config = LineConfig::new().unit(unit);
+            assert_eq!(end(input, config), (1, column));
+            assert_eq!(end(input.as_bytes(), config), (1, column));
+        }
+
+        // Errors are reported in configured columns, and located in the source from them.
+        let config = LineConfig::new().tab_width(8).crlf(true);
+        let mut parser = range("a\r\n\t").with(item(b'b'));
+        let (error, _) = parser
+            .parse(State::<&str, LinePosition>::new(
+                "a\r\n\tc",
+                LinePosition::with_config(config),
+            ))
+            .unwrap_err();
+        assert_eq!(error.position, LinePosition::from((2, 9)));
+        assert_eq!(
+            <LinePosition as Position<&str>>::offset_in(&error.position, b"a\r\n\tc"),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_line_index() {
+        let source = "ab\n\u{e9}x\n\n\tz";
+        let index = LineIndex::new(source);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_start(2), Some(3));
+        assert_eq!(index.line_start(0), None);
+        assert_eq!(index.line_start(5), None);
+
+        for &(offset, line, column) in &[
+            (0, 1, 1),
+            (2, 1, 3),
+            (3, 2, 1),
+            (5, 2, 2),
+            (7, 3, 1),
+            (8, 4, 1),
+            (10, 4, 3),
+        ] {
+            let position = SourcePosition::from((offset, line, column));
+            assert_eq!(index.position(offset), Some(position));
+            assert_eq!(index.offset(position), Some(offset));
+        }
+        assert_eq!(index.position(11), None);
+        assert_eq!(index.offset((1, 4)), None);
+        assert_eq!(index.offset((5, 1)), None);
+
+        let index = index.config(LineConfig::new().tab_width(4));
+        assert_eq!(index.line_position(9), Some(LinePosition::from((4, 5))));
+        assert_eq!(index.offset((4, 5)), Some(9));
+        assert_eq!(index.offset((4, 3)), None);
+
+        // Offsets tracked while parsing convert to the line and column tracked alongside them.
+        fn parser<S: Stream>() -> impl Parser<Stream = S, Output = S::Item> {
+            range("ab\n\u{e9}").with(item(b'y'))
+        }
+        let (error, _) = parser()
+            .parse(State::<&str, SourcePosition>::from(source))
+            .unwrap_err();
+        let (offset_error, _) = parser()
+            .parse(State::<&str, IndexPosition>::from(source))
+            .unwrap_err();
+        assert_eq!(
+            LineIndex::new(source).position(offset_error.position.0),
+            Some(error.position)
+        );
+
+        // Items popped one at a time advance the offset by their width in bytes, as ranges do.
+        let mut parser = many::<String, _>(any()).skip(item(b'!'));
+        test_parser!(State<&str, IndexPosition> => String | parser, {
+            "\u{e9}x" => err(Error::eoi().expected_item('!').at(3)),
+        });
+        let mut stream = State::<&str, IndexPosition>::from(source);
+        for _ in 0..4 {
+            stream.pop();
+        }
+        assert_eq!(stream.position().0, 5);
+        assert_eq!(
+            LineIndex::new(source).position(stream.position().0),
+            Some(SourcePosition::from((5, 2, 2)))
+        );
+    }
+
+    #[test]
+    fn test_source_position() {
+        let mut parser = range("\u{e9}\n").then(range("ab"));
+        test_parser!(State<&str, SourcePosition> => Vec<&str> | parser, {
+            "\u{e9}\nab." => ok(vec!["\u{e9}\n", "ab"], (".", (5, 2, 3))),
+            "\u{e9}\nax" => err(
+                Error::item('x')
+                    .expected_range("ab")
+                    .at((4, 2, 2))
+                    .spanning(((3, 2, 1), (5, 2, 3)))
+            ),
+        });
+        let mut parser = range("\u{e9}\n").then(range("ab"));
+        test_parser!(State<&[u8], SourcePosition> => Vec<&[u8]> | parser, {
+            "\u{e9}\nab.".as_bytes() => ok(
+                vec!["\u{e9}\n".as_bytes(), &b"ab"[..]],
+                (&b"."[..], (5, 2, 3)),
+            ),
+        });
+    }
+
+    #[test]
+    fn test_file_position() {
+        // A FilePosition tracks its SourcePosition as if it were parsed on its own.
+        let input = "\u{e9}\nab.";
+        let mut stream = State::<&str, FilePosition>::from((input, FilePosition::from(FileId(3))));
+        let mut source = State::<&str, SourcePosition>::from(input);
+        stream.pop();
+        source.pop();
+        stream.range(3);
+        source.range(3);
+        assert_eq!(stream.position.file, FileId(3));
+        assert_eq!(stream.position.position, source.position);
+        assert_eq!(stream.position.line, 2);
+        assert_eq!(stream.position.to_string(), "file #3, line 2, col 3");
+        assert_eq!(
+            <FilePosition as Position<&str>>::to_json(&stream.position),
+            json!({ "file": 3, "offset": 5, "line": 2, "column": 3 })
+        );
     }
 }
@@ -0,0 +1,405 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::{NullPosition, RangeStream, TextRange, TokenStream, Tokens};
+use traits::{AsBytes, StrLike};
+
+/// SharedBytes is an owned byte `Stream`: a cheaply cloneable slice of a shared, immutable
+/// buffer.
+///
+/// Cloning a `SharedBytes` (including [`TokenStream::backup`]) and taking ranges of it are O(1),
+/// since they only adjust the bounds of the slice. Ranges are `SharedBytes` sharing the same
+/// buffer. It is also the [`Range`](TokenStream::Range) type of
+/// [`BufferedStream`](super::BufferedStream).
+///
+/// Since `SharedBytes` owns its input, it can be moved between threads and stored alongside the
+/// parsers that use it.
+#[derive(Clone)]
+pub struct SharedBytes {
+    data: Arc<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl SharedBytes {
+    /// Returns the bytes of the slice.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.data[self.start..self.end]
+    }
+}
+
+impl Deref for SharedBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for SharedBytes {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsBytes for SharedBytes {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl fmt::Debug for SharedBytes {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_slice().fmt(f)
+    }
+}
+
+impl PartialEq for SharedBytes {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for SharedBytes {}
+
+impl Hash for SharedBytes {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+impl<'a> From<&'a [u8]> for SharedBytes {
+    fn from(bytes: &'a [u8]) -> Self {
+        SharedBytes {
+            data: Arc::from(bytes),
+            start: 0,
+            end: bytes.len(),
+        }
+    }
+}
+
+impl From<Arc<[u8]>> for SharedBytes {
+    fn from(data: Arc<[u8]>) -> Self {
+        let end = data.len();
+        SharedBytes {
+            data,
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl From<Vec<u8>> for SharedBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        let end = bytes.len();
+        SharedBytes {
+            data: Arc::from(bytes),
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl From<String> for SharedBytes {
+    fn from(s: String) -> Self {
+        SharedBytes::from(s.into_bytes())
+    }
+}
+
+impl<'a> From<&'a str> for SharedBytes {
+    fn from(s: &'a str) -> Self {
+        SharedBytes::from(s.as_bytes())
+    }
+}
+
+impl StrLike for SharedBytes {
+    fn from_utf8(&self) -> Result<&str, ()> {
+        self.as_slice().from_utf8()
+    }
+}
+
+impl RangeStream for SharedBytes {
+    fn empty() -> Self {
+        SharedBytes::from(&[][..])
+    }
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+    fn into_string(self) -> Result<String, Self> {
+        String::from_utf8(self.as_slice().to_vec()).map_err(|_| self)
+    }
+}
+
+impl TextRange for SharedBytes {
+    fn from_str(s: &'static str) -> Self {
+        SharedBytes::from(s)
+    }
+}
+
+impl TokenStream for SharedBytes {
+    type Stream = Self;
+    type Position = NullPosition;
+    type Item = u8;
+    type Range = Self;
+
+    fn peek(&self) -> Option<Self::Item> {
+        self.as_slice().first().cloned()
+    }
+
+    fn pop(&mut self) -> Option<Self::Item> {
+        let item = self.peek();
+        if item.is_some() {
+            self.start += 1;
+        }
+        item
+    }
+
+    fn tokens(&self) -> Tokens<'_, Self::Item> {
+        Tokens::new(self.as_slice().iter().cloned())
+    }
+
+    fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
+        (to_idx <= RangeStream::len(self)).then(|| {
+            let head = SharedBytes {
+                data: self.data.clone(),
+                start: self.start,
+                end: self.start + to_idx,
+            };
+            self.start += to_idx;
+            head
+        })
+    }
+
+    fn as_range(&mut self) -> Self::Range {
+        let range = self.clone();
+        self.start = self.end;
+        range
+    }
+
+    fn position(&self) -> &Self::Position {
+        &NullPosition
+    }
+
+    fn distance(&self, end: &Self) -> usize {
+        end.start - self.start
+    }
+}
+
+/// SharedStr is an owned text `Stream`: a cheaply cloneable slice of a shared, immutable string.
+///
+/// Like [`SharedBytes`], cloning and taking ranges are O(1), and ranges are `SharedStr`s sharing
+/// the same buffer. Ranges are measured in bytes, as with `&str`.
+#[derive(Clone)]
+pub struct SharedStr {
+    data: Arc<str>,
+    start: usize,
+    end: usize,
+}
+
+impl SharedStr {
+    /// Returns the text of the slice.
+    pub fn as_str(&self) -> &str {
+        &self.data[self.start..self.end]
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for SharedStr {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsBytes for SharedStr {
+    fn as_bytes(&self) -> &[u8] {
+        self.as_str().as_bytes()
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for SharedStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for SharedStr {}
+
+impl Hash for SharedStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(s: String) -> Self {
+        let end = s.len();
+        SharedStr {
+            data: Arc::from(s),
+            start: 0,
+            end,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for SharedStr {
+    fn from(s: &'a str) -> Self {
+        SharedStr {
+            data: Arc::from(s),
+            start: 0,
+            end: s.len(),
+        }
+    }
+}
+
+impl StrLike for SharedStr {
+    fn from_utf8(&self) -> Result<&str, ()> {
+        Ok(self.as_str())
+    }
+}
+
+impl RangeStream for SharedStr {
+    fn empty() -> Self {
+        SharedStr::from("")
+    }
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+    fn into_string(self) -> Result<String, Self> {
+        Ok(String::from(self.as_str()))
+    }
+}
+
+impl TextRange for SharedStr {
+    fn from_str(s: &'static str) -> Self {
+        SharedStr::from(s)
+    }
+}
+
+impl TokenStream for SharedStr {
+    type Stream = Self;
+    type Position = NullPosition;
+    type Item = char;
+    type Range = Self;
+
+    fn peek(&self) -> Option<Self::Item> {
+        self.as_str().chars().next()
+    }
+
+    fn pop(&mut self) -> Option<Self::Item> {
+        let item = self.peek();
+        if let Some(c) = item {
+            self.start += c.len_utf8();
+        }
+        item
+    }
+
+    fn tokens(&self) -> Tokens<'_, Self::Item> {
+        Tokens::new(self.as_str().chars())
+    }
+
+    fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
+        self.as_str().is_char_boundary(to_idx).then(|| {
+            let head = SharedStr {
+                data: self.data.clone(),
+                start: self.start,
+                end: self.start + to_idx,
+            };
+            self.start += to_idx;
+            head
+        })
+    }
+
+    fn as_range(&mut self) -> Self::Range {
+        let range = self.clone();
+        self.start = self.end;
+        range
+    }
+
+    fn position(&self) -> &Self::Position {
+        &NullPosition
+    }
+
+    fn distance(&self, end: &Self) -> usize {
+        end.start - self.start
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::Error;
+    use parser::item::{ascii, item};
+    use parser::range::range;
+    use parser::repeat::{many1, sep_by};
+    use parser::Parser;
+    use stream::{IndexedStream, LinePosition, State};
+
+    #[test]
+    fn test_shared_str() {
+        let mut parser = range("let ").with(many1::<String, _>(ascii::letter()));
+        test_parser!(SharedStr => String | parser, {
+            String::from("let x = 1") => ok("x".to_string(), " = 1"),
+            "let 1" => err(Error::item('1').expected("an ascii letter")),
+        });
+
+        let mut stream = SharedStr::from("añb");
+        assert_eq!(stream.range(2), None);
+        let head = stream.range(3).unwrap();
+        assert_eq!((head.as_str(), stream.as_str()), ("añ", "b"));
+        // Ranges share the stream's buffer.
+        assert!(Arc::ptr_eq(&head.data, &stream.data));
+    }
+
+    #[test]
+    fn test_shared_bytes() {
+        let mut parser = sep_by::<Vec<_>, _, _>(range("ab"), item(b','));
+        test_parser!(IndexedStream<SharedBytes> => Vec<SharedBytes> | parser, {
+            SharedBytes::from(b"ab,ab;".to_vec()) => ok(
+                vec![SharedBytes::from("ab"), SharedBytes::from("ab")],
+                (SharedBytes::from(";"), 5)
+            ),
+        });
+    }
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn test_shared_stream_thread() {
+        assert_send::<SharedStr>();
+        assert_send::<State<SharedStr, LinePosition>>();
+        assert_send::<State<&'static str, LinePosition>>();
+
+        let input = String::from("foo\nbar baz");
+        let handle = std::thread::spawn(move || {
+            let stream = State::<_, LinePosition>::from(SharedStr::from(input));
+            range("foo\nbar")
+                .skip(item(b'!'))
+                .parse(stream)
+                .unwrap_err()
+                .0
+        });
+        let error = handle.join().unwrap();
+        assert_eq!(error, Error::item(' ').expected_item('!').at((2, 4)));
+    }
+}
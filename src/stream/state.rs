@@ -1,40 +1,157 @@
+use std::fmt::{self, Debug};
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard};
+
 use super::position::Position;
-use super::{Stream, Tokens};
+use super::{RecoverStream, StateStream, TokenStream, Tokens};
+use error::Error;
+
+/// The errors recorded on a `State`, shared with its backups so that cloning it doesn't copy them.
+///
+/// Each stream sees the first `len` errors, the ones recorded before it. Errors past that were
+/// recorded by branches that have since backtracked, and are dropped when the stream records an
+/// error or is restored.
+///
+/// The errors are behind a `Mutex` so that a `State` can be moved between threads.
+struct Recorded<E> {
+    errors: Arc<Mutex<Vec<E>>>,
+    len: usize,
+}
+
+impl<E> Recorded<E> {
+    fn new() -> Self {
+        Recorded {
+            errors: Arc::new(Mutex::new(Vec::new())),
+            len: 0,
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<E>> {
+        self.errors.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn push(&mut self, error: E) {
+        let len = {
+            let mut errors = self.lock();
+            errors.truncate(self.len);
+            errors.push(error);
+            errors.len()
+        };
+        self.len = len;
+    }
+
+    /// Drops the errors recorded after this stream.
+    fn truncate(&self) {
+        self.lock().truncate(self.len);
+    }
+
+    fn take(&mut self) -> Vec<E> {
+        self.truncate();
+        self.len = 0;
+        mem::take(&mut *self.lock())
+    }
+}
+
+impl<E> Clone for Recorded<E> {
+    fn clone(&self) -> Self {
+        Recorded {
+            errors: self.errors.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<E: Debug> Debug for Recorded<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list()
+            .entries(self.lock().iter().take(self.len))
+            .finish()
+    }
+}
+
+impl<E: PartialEq> PartialEq for Recorded<E> {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.errors, &other.errors) {
+            let errors = self.lock();
+            return errors
+                .iter()
+                .take(self.len)
+                .eq(errors.iter().take(other.len));
+        }
+        let (errors, other_errors) = (self.lock(), other.lock());
+        errors
+            .iter()
+            .take(self.len)
+            .eq(other_errors.iter().take(other.len))
+    }
+}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct State<S: Stream, X: Position<S>> {
+impl<E: Eq> Eq for Recorded<E> {}
+
+/// State wraps a `Stream`, tracking its [`Position`] and carrying user-defined state of type `U`.
+///
+/// The user state is available to parsers through [`StateStream`]. Since it's part of the
+/// stream, it's cloned by [`TokenStream::backup`] and restored when parsing backtracks, so state
+/// that is expensive to clone is best kept behind an `Rc` or in a persistent data structure.
+///
+/// `State` also records the errors that parsers recover from (see [`RecoverStream`]). Recorded
+/// errors are shared between a `State` and its backups, so backing up is O(1) however many errors
+/// have been recorded, and errors recorded by a branch that backtracks are dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State<S: TokenStream, X: Position<S>, U: Clone + Debug = ()> {
     pub stream: S,
     pub position: X,
+    /// User-defined state.
+    pub user: U,
+    errors: Recorded<Error<Self>>,
 }
 
-impl<S: Stream, X: Position<S>> State<S, X> {
+impl<S: TokenStream, X: Position<S>, U: Clone + Debug + Default> State<S, X, U> {
     pub fn new<T: Into<X>>(stream: S, position: T) -> Self {
+        State::with_state(stream, position, U::default())
+    }
+}
+
+impl<S: TokenStream, X: Position<S>, U: Clone + Debug> State<S, X, U> {
+    /// Create a `State` at `position` with the user state `user`.
+    pub fn with_state<T: Into<X>>(stream: S, position: T, user: U) -> Self {
         State {
             stream,
             position: position.into(),
+            user,
+            errors: Recorded::new(),
         }
     }
 }
 
-impl<S: Stream, X: Position<S>> From<S> for State<S, X> {
+impl<S: TokenStream, X: Position<S>, U: Clone + Debug + Default> From<S> for State<S, X, U> {
     fn from(stream: S) -> Self {
-        State {
-            stream,
-            position: Default::default(),
-        }
+        State::new(stream, X::default())
+    }
+}
+
+impl<S: TokenStream + Hash, X: Position<S>, U: Clone + Debug + Hash> Hash for State<S, X, U> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.stream.hash(state);
+        self.position.hash(state);
+        self.user.hash(state);
     }
 }
 
-impl<S: Stream, X: Position<S>, T: Into<X>> From<(S, T)> for State<S, X> {
+impl<S: TokenStream, X: Position<S>, U: Clone + Debug + Default, T: Into<X>> From<(S, T)>
+    for State<S, X, U>
+{
     fn from((stream, pos): (S, T)) -> Self {
         State::new(stream, pos)
     }
 }
 
-impl<S, X> Stream for State<S, X>
+impl<S, X, U> TokenStream for State<S, X, U>
 where
-    S: Stream,
+    S: TokenStream,
     X: Position<S>,
+    U: Clone + Debug,
 {
     type Stream = S;
     type Position = X;
@@ -48,6 +165,7 @@ where
     fn pop(&mut self) -> Option<Self::Item> {
         self.stream.pop().map(|item| {
             self.position.update(&item);
+            self.position.followed_by(self.stream.peek().as_ref());
             item
         })
     }
@@ -59,6 +177,7 @@ where
     fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
         self.stream.range(to_idx).map(|range| {
             self.position.update_range(&range);
+            self.position.followed_by(self.stream.peek().as_ref());
             range
         })
     }
@@ -66,10 +185,82 @@ where
     fn as_range(&mut self) -> Self::Range {
         let range = self.stream.as_range();
         self.position.update_range(&range);
+        self.position.followed_by(None);
         range
     }
 
     fn position(&self) -> &Self::Position {
         &self.position
     }
+
+    fn distance(&self, end: &Self) -> usize {
+        self.stream.distance(&end.stream)
+    }
+
+    fn is_partial(&self) -> bool {
+        self.stream.is_partial()
+    }
+
+    fn restore(&mut self, backup: Self) {
+        backup.errors.truncate();
+        *self = backup;
+    }
+
+    fn checkpoint(&self) -> Self {
+        State {
+            stream: self.stream.checkpoint(),
+            position: self.position.clone(),
+            user: self.user.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+
+    fn restore_checkpoint(&mut self, checkpoint: Self) -> bool {
+        let State {
+            stream,
+            position,
+            user,
+            errors,
+        } = checkpoint;
+        if !self.stream.restore_checkpoint(stream) {
+            return false;
+        }
+        errors.truncate();
+        self.position = position;
+        self.user = user;
+        self.errors = errors;
+        true
+    }
+}
+
+impl<S, X, U> RecoverStream for State<S, X, U>
+where
+    S: TokenStream,
+    X: Position<S>,
+    U: Clone + Debug,
+{
+    fn record_error(&mut self, error: Error<Self>) {
+        self.errors.push(error);
+    }
+
+    fn take_errors(&mut self) -> Vec<Error<Self>> {
+        self.errors.take()
+    }
+}
+
+impl<S, X, U> StateStream for State<S, X, U>
+where
+    S: TokenStream,
+    X: Position<S>,
+    U: Clone + Debug,
+{
+    type State = U;
+
+    fn state(&self) -> &U {
+        &self.user
+    }
+
+    fn state_mut(&mut self) -> &mut U {
+        &mut self.user
+    }
 }
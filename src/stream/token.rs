@@ -0,0 +1,178 @@
+use std::fmt::Debug;
+
+use super::{NullPosition, RangeStream, TokenStream, Tokens};
+
+/// TokenSlice is a `TokenStream` over a slice of tokens, such as the output of a separate lexer.
+///
+/// Its items are references to the tokens, so the token type only needs to implement `PartialEq`
+/// and `Debug`; it doesn't need to be `Copy`. Ranges are `TokenSlice`s, and the stream can be
+/// wrapped in a [`State`](super::State) to track positions, e.g. as an
+/// [`IndexedStream`](super::IndexedStream) counting tokens.
+///
+/// Since [`item`](::parser::item::item) and [`one_of`](::parser::item::one_of) take bytes, tokens
+/// are matched with [`token`](::parser::item::token),
+/// [`one_of_tokens`](::parser::item::one_of_tokens) and
+/// [`none_of_tokens`](::parser::item::none_of_tokens) instead.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct TokenSlice<'a, T> {
+    tokens: &'a [T],
+}
+
+impl<'a, T> TokenSlice<'a, T> {
+    pub fn new(tokens: &'a [T]) -> Self {
+        TokenSlice { tokens }
+    }
+
+    /// Returns the remaining tokens.
+    pub fn as_slice(&self) -> &'a [T] {
+        self.tokens
+    }
+}
+
+impl<'a, T> Clone for TokenSlice<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for TokenSlice<'a, T> {}
+
+impl<'a, T> From<&'a [T]> for TokenSlice<'a, T> {
+    fn from(tokens: &'a [T]) -> Self {
+        TokenSlice::new(tokens)
+    }
+}
+
+impl<'a, T> From<&'a Vec<T>> for TokenSlice<'a, T> {
+    fn from(tokens: &'a Vec<T>) -> Self {
+        TokenSlice::new(tokens)
+    }
+}
+
+impl<'a, T: PartialEq + Debug> RangeStream for TokenSlice<'a, T> {
+    fn empty() -> Self {
+        TokenSlice::new(&[])
+    }
+    fn len(&self) -> usize {
+        self.tokens.len()
+    }
+    fn into_string(self) -> Result<String, Self> {
+        Err(self)
+    }
+}
+
+impl<'a, T: PartialEq + Debug> TokenStream for TokenSlice<'a, T> {
+    type Stream = Self;
+    type Position = NullPosition;
+    type Item = &'a T;
+    type Range = Self;
+
+    fn peek(&self) -> Option<Self::Item> {
+        self.tokens.first()
+    }
+
+    fn pop(&mut self) -> Option<Self::Item> {
+        let tokens = self.tokens;
+        tokens.split_first().map(|(head, tail)| {
+            self.tokens = tail;
+            head
+        })
+    }
+
+    fn tokens(&self) -> Tokens<'_, Self::Item> {
+        Tokens::new(self.tokens.iter())
+    }
+
+    fn range(&mut self, to_idx: usize) -> Option<Self::Range> {
+        let tokens = self.tokens;
+        (to_idx <= tokens.len()).then(|| {
+            let (head, tail) = tokens.split_at(to_idx);
+            self.tokens = tail;
+            TokenSlice::new(head)
+        })
+    }
+
+    fn as_range(&mut self) -> Self::Range {
+        let range = *self;
+        self.tokens = &[];
+        range
+    }
+
+    fn position(&self) -> &Self::Position {
+        &NullPosition
+    }
+
+    fn distance(&self, end: &Self) -> usize {
+        self.tokens.len() - end.tokens.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::{Error, Info};
+    use parser::item::{eoi_, none_of_tokens, one_of_tokens, satisfy, token};
+    use parser::repeat::{many1, sep_by, sep_by1};
+    use parser::Parser;
+    use stream::IndexedStream;
+
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        Ident(String),
+        Number(f64),
+        Plus,
+        Minus,
+        Comma,
+    }
+
+    fn lex(input: &str) -> Vec<Token> {
+        input
+            .split_whitespace()
+            .map(|word| match word {
+                "+" => Token::Plus,
+                "-" => Token::Minus,
+                "," => Token::Comma,
+                _ => match word.parse() {
+                    Ok(n) => Token::Number(n),
+                    Err(_) => Token::Ident(word.to_string()),
+                },
+            })
+            .collect()
+    }
+
+    fn number<'a>() -> impl Parser<Stream = IndexedStream<TokenSlice<'a, Token>>, Output = f64> {
+        satisfy(|t: &&Token| matches!(t, Token::Number(_)))
+            .map(|t: &Token| match *t {
+                Token::Number(n) => n,
+                _ => unreachable!(),
+            })
+            .expect("a number")
+    }
+
+    #[test]
+    fn test_token_slice() {
+        let tokens = lex("1 + 2 - 3 , 4");
+        let invalid = lex("1 + x");
+        let terms = sep_by1::<Vec<_>, _, _>(number(), one_of_tokens(&[Token::Plus, Token::Minus]));
+        let mut parser = sep_by::<Vec<_>, _, _>(terms, token(&Token::Comma)).skip(eoi_());
+        let (result, stream) = parser
+            .parse(IndexedStream::from(TokenSlice::from(&tokens)))
+            .unwrap();
+        assert_eq!(result, Some(vec![vec![1.0, 2.0, 3.0], vec![4.0]]));
+        assert_eq!(stream.position, 7.into());
+
+        let (error, _) = parser
+            .parse(IndexedStream::from(TokenSlice::from(&invalid)))
+            .unwrap_err();
+        assert_eq!(error, Error::item(&invalid[1]).expected(Info::EOI).at(1));
+        assert_eq!(
+            error.to_string(),
+            "parsing failed at index 1: unexpected item Plus: expected end of input"
+        );
+
+        let mut parser = many1::<Vec<_>, _>(none_of_tokens(&[Token::Comma]));
+        let (result, stream) = parser.parse(TokenSlice::from(&tokens)).unwrap();
+        assert_eq!(result, Some(tokens[..5].iter().collect()));
+        assert_eq!(stream.as_slice(), &tokens[5..]);
+    }
+}